@@ -8,7 +8,7 @@ fn to_arr(s: &str) -> [[char; 9]; 9] {
     let mut arr = [['.'; 9]; 9];
     let mut i = 0;
     for c in s.chars() {
-        if '1' <= c && c <= '9' || c == '.' {
+        if ('1'..='9').contains(&c) || c == '.' {
             let (row, col) = (i / 9, i % 9);
             arr[row][col] = c;
             i += 1;
@@ -36,12 +36,12 @@ fn result_to_str(c: &mut Criterion) {
     let mut group = c.benchmark_group("result_to_str");
     group.bench_function("chars", |b| {
         b.iter_batched(
-            || setup(),
+            setup,
             |(board, s)| {
                 let mut s = s.clone();
-                for i in 0..9 {
-                    for j in 0..9 {
-                        s.push(board[i][j].to_string().chars().nth(0).unwrap());
+                for row in board.iter() {
+                    for cell in row.iter() {
+                        s.push(cell.to_string().chars().next().unwrap());
                     }
                 }
                 black_box(s);
@@ -51,12 +51,12 @@ fn result_to_str(c: &mut Criterion) {
     });
     group.bench_function("lucky", |b| {
         b.iter_batched(
-            || setup(),
+            setup,
             |(board, s)| {
                 let mut s = s.clone();
-                for i in 0..9 {
-                    for j in 0..9 {
-                        s.push(board[i][j].lucky());
+                for row in board.iter() {
+                    for cell in row.iter() {
+                        s.push(cell.lucky());
                     }
                 }
                 black_box(s);
@@ -140,7 +140,7 @@ fn ripple_solver(c: &mut Criterion) {
         b.iter_batched(
             || to_arr(EASY),
             |arr| {
-                let mut arr = arr.clone();
+                let mut arr = arr;
                 black_box(Ripple::solve_arr(&mut arr));
             },
             BatchSize::SmallInput,
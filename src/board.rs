@@ -1,25 +1,46 @@
-use std::{fmt, sync::Once};
+use std::{fmt, sync::OnceLock};
 
 use crate::candidates::Candidates;
+use crate::cell::Cell;
+use crate::variant::Variant;
 
-static INIT: Once = Once::new();
-static mut PEERS: [[[(u8, u8); 20]; 9]; 9] = [[[(0, 0); 20]; 9]; 9];
+/// peers of every cell, one table for each combination of variant rules
+static PEERS: [OnceLock<Peers>; Variant::COUNT] = [const { OnceLock::new() }; Variant::COUNT];
+type Peers = [Vec<Cell>; 81];
 
 /// Board of the game
 ///
-/// A board has 9*9 cells
+/// A board has 9*9 cells, and the variant rules deciding peers of cells.
+///
+/// With feature `serde`, it's serialized as below, `solved` has the cells of one
+/// candidate, '.' for others, and is also read as `givens` for a puzzle.
+/// `candidates` is optional while deserializing, and `variant` is omitted for
+/// classic rules. A board of a cell without candidates can't be serialized, as
+/// it's rejected while deserializing:
+/// ```text
+/// {
+///   "solved": "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+///   "candidates": ["8", "1246", "24569", ...],
+///   "variant": ["anti-knight"]
+/// }
+/// ```
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub struct Board([[Candidates; 9]; 9]);
+pub struct Board {
+    cells: [[Candidates; 9]; 9],
+    variant: Variant,
+}
 impl Board {
     pub fn new(s: &str) -> Option<Self> {
-        Self::must_init();
-
         let mut b: Self = Default::default();
         let mut i = 0;
         let mut j = 0;
         for c in s.chars() {
             if ('1'..='9').contains(&c) || c == '.' {
-                b.0[i][j] = Candidates::from(c);
+                // too many cells
+                if i == 9 {
+                    return None;
+                }
+                b.cells[i][j] = Candidates::from(c);
                 if j < 8 {
                     j += 1;
                 } else {
@@ -36,29 +57,125 @@ impl Board {
             Some(b)
         }
     }
-    pub fn peers_of(row: usize, col: usize) -> &'static [(u8, u8); 20] {
-        unsafe { &PEERS[row][col] }
+    /// board from a pencil-mark grid, e.g. the output of `Display`, or the ones
+    /// of HoDoKu and SudokuWiki:
+    /// ```text
+    /// .----------------.----------------.------------------.
+    /// | 57   457   2   | 8    347  6    | 9    1     3     |
+    /// ...
+    /// ```
+    /// Lines with cells are the ones between '|', bounds and labels outside are
+    /// ignored, each cell is a group of candidates, or 'X' for none as printed
+    /// by `Display`.
+    pub fn from_pencil_marks(s: &str) -> Option<Self> {
+        let mut b: Self = Default::default();
+        let mut i = 0;
+        for line in s.lines() {
+            let (first, last) = (line.find('|'), line.rfind('|'));
+            let inner = match (first, last) {
+                (Some(first), Some(last)) if first < last => &line[first + 1..last],
+                _ => continue,
+            };
+            // bound lines, e.g. "|---+---|"
+            if !inner.chars().any(|c| c.is_ascii_digit() || c == 'X') {
+                continue;
+            }
+
+            let mut j = 0;
+            for token in inner.split(|c: char| c == '|' || c.is_whitespace()) {
+                if token.is_empty() {
+                    continue;
+                }
+                if i >= 9 || j >= 9 {
+                    return None;
+                }
+                if token == "X" {
+                    b.cells[i][j] = Candidates::from_bits(0);
+                    j += 1;
+                    continue;
+                }
+                if !token.chars().all(|c| ('1'..='9').contains(&c)) {
+                    return None;
+                }
+                let bits = token
+                    .chars()
+                    .fold(0, |bits, c| bits | Candidates::from(c).bits());
+                b.cells[i][j] = Candidates::from_bits(bits);
+                j += 1;
+            }
+            if j != 9 {
+                return None;
+            }
+            i += 1;
+        }
+
+        if i != 9 {
+            None
+        } else {
+            Some(b)
+        }
+    }
+    /// mask cells by parity while loading, `s` has 81 marks in reading order:
+    /// 'e' for even only, 'o' for odd only, '.' for no mark, others are ignored.
+    /// false if marks are invalid or conflict with the givens
+    pub fn mask_parity(&mut self, s: &str) -> bool {
+        const EVEN: u16 = 0b010101010;
+        const ODD: u16 = 0b101010101;
+
+        let marks = s
+            .chars()
+            .filter(|c| ['e', 'E', 'o', 'O', '.'].contains(c))
+            .collect::<Vec<_>>();
+        if marks.len() != 81 {
+            return false;
+        }
+        let mut b = *self;
+        for (k, mark) in marks.into_iter().enumerate() {
+            let bits = match mark {
+                'e' | 'E' => EVEN,
+                'o' | 'O' => ODD,
+                _ => continue,
+            };
+            let (i, j) = (k / 9, k % 9);
+            let cs = Candidates::from_bits(b.cells[i][j].bits() & bits);
+            if !cs.is_valid() {
+                return false;
+            }
+            b.cells[i][j] = cs;
+        }
+        *self = b;
+        true
+    }
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+    }
+    /// cells can't share a digit with the cell, the count depends on variant rules
+    pub fn peers_of(&self, c: Cell) -> &'static [Cell] {
+        &Self::peers(self.variant)[c.index()]
     }
-    pub fn cell(&self, i: usize, j: usize) -> Candidates {
-        self.0[i][j]
+    pub fn cell(&self, c: Cell) -> Candidates {
+        self.cells[c.row()][c.col()]
     }
-    pub fn set_cell(&mut self, i: usize, j: usize, cs: Candidates) {
-        self.0[i][j] = cs;
+    pub fn set_cell(&mut self, c: Cell, cs: Candidates) {
+        self.cells[c.row()][c.col()] = cs;
     }
-    pub fn is_done(&self, i: usize, j: usize) -> bool {
-        self.0[i][j].is_done()
+    pub fn is_done(&self, c: Cell) -> bool {
+        self.cell(c).is_done()
     }
-    pub fn lucky(&self, i: usize, j: usize) -> char {
-        self.0[i][j].lucky()
+    pub fn lucky(&self, c: Cell) -> char {
+        self.cell(c).lucky()
     }
-    pub fn substract(&mut self, i: usize, j: usize, cs: Candidates) {
-        self.0[i][j].substract(&cs)
+    pub fn substract(&mut self, c: Cell, cs: Candidates) {
+        self.cells[c.row()][c.col()].substract(&cs)
     }
     pub fn iter(&self) -> std::slice::Iter<'_, [Candidates; 9]> {
-        self.0.iter()
+        self.cells.iter()
     }
     pub fn write_arr(&self, arr: &mut [[char; 9]; 9]) {
-        for (p_line, b_line) in arr.iter_mut().zip(self.0.iter()) {
+        for (p_line, b_line) in arr.iter_mut().zip(self.cells.iter()) {
             for (p_char, cell) in p_line.iter_mut().zip(b_line.iter()) {
                 // CAUTION: `to_string().chars().nth(0).unwrap()` takes 3000 ns...
                 // *p_char = g_cell.to_string().chars().nth(0).unwrap();
@@ -67,44 +184,22 @@ impl Board {
         }
     }
 
-    fn must_init() {
-        INIT.call_once(|| unsafe {
-            for (row, line) in PEERS.iter_mut().enumerate() {
-                for (col, cell_peers) in line.iter_mut().enumerate() {
-                    let mut k = 0;
-                    for i in 0..9 {
-                        // same row
-                        if i != col {
-                            cell_peers[k] = (row as u8, i as u8);
-                            k += 1;
-                        }
-                        // same col
-                        if i != row {
-                            cell_peers[k] = (i as u8, col as u8);
-                            k += 1;
-                        }
-                    }
-                    // same block
-                    let (r_s, c_s) = (row / 3 * 3, col / 3 * 3);
-                    for i in r_s..r_s + 3 {
-                        for j in c_s..c_s + 3 {
-                            if i != row && j != col {
-                                PEERS[row][col][k] = (i as u8, j as u8);
-                                k += 1;
-                            }
-                        }
-                    }
-                    PEERS[row][col].sort_unstable();
-                }
+    fn peers(variant: Variant) -> &'static Peers {
+        PEERS[variant.index()].get_or_init(|| {
+            let mut peers: Peers = std::array::from_fn(|_| Vec::new());
+            for (c, cell_peers) in Cell::all().zip(peers.iter_mut()) {
+                // in reading order, so the peers are sorted
+                cell_peers.extend(Cell::all().filter(|&p| variant.sees(c, p)));
             }
-        });
+            peers
+        })
     }
 }
 
 impl From<[[char; 9]; 9]> for Board {
     fn from(puzzle: [[char; 9]; 9]) -> Self {
         let mut b = Self::default();
-        for (p_line, b_line) in puzzle.iter().zip(b.0.iter_mut()) {
+        for (p_line, b_line) in puzzle.iter().zip(b.cells.iter_mut()) {
             for (&p_char, b_cell) in p_line.iter().zip(b_line.iter_mut()) {
                 *b_cell = Candidates::from(p_char);
             }
@@ -131,7 +226,7 @@ impl fmt::Display for Board {
     /// |  2    1    7   |   9   368   4   |  5   368  368 |
     /// +--------------------------------------------------+
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let g = &self.0;
+        let g = &self.cells;
         // max width of each col, for alignment
         let cols_width = (0..9)
             .map(|col| (0..9).map(|row| g[row][col].len()).max().unwrap())
@@ -157,7 +252,7 @@ impl fmt::Display for Board {
                 // spaces required for this cell, if len==0, will show as 'X'
                 let spaces = w - std::cmp::max(1, line[j].len());
                 // leading spaces, put one more as leading
-                for _ in 0..((spaces + 1) / 2) {
+                for _ in 0..spaces.div_ceil(2) {
                     f.write_str(" ")?
                 }
                 // candidates
@@ -179,3 +274,176 @@ impl fmt::Display for Board {
         f.write_str("+\n")
     }
 }
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BoardRepr {
+    #[serde(alias = "givens")]
+    solved: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    candidates: Option<Vec<Candidates>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    variant: Vec<String>,
+}
+#[cfg(feature = "serde")]
+impl serde::Serialize for Board {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let cells = self.cells.iter().flatten();
+        if cells.clone().any(|cs| cs.is_empty()) {
+            return Err(serde::ser::Error::custom("a cell has no candidate"));
+        }
+        BoardRepr {
+            solved: cells
+                .clone()
+                .map(|cs| if cs.len() == 1 { cs.lucky() } else { '.' })
+                .collect(),
+            candidates: Some(cells.copied().collect()),
+            variant: self.variant.names().iter().map(|s| s.to_string()).collect(),
+        }
+        .serialize(serializer)
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let repr = BoardRepr::deserialize(deserializer)?;
+        let mut b =
+            Self::new(&repr.solved).ok_or_else(|| D::Error::custom("invalid solved cells"))?;
+        if let Some(candidates) = repr.candidates {
+            if candidates.len() != 81 {
+                return Err(D::Error::custom("candidates of 81 cells are required"));
+            }
+            for (k, cs) in candidates.into_iter().enumerate() {
+                let (i, j) = (k / 9, k % 9);
+                if b.cells[i][j].is_done() && b.cells[i][j] != cs {
+                    return Err(D::Error::custom("candidates conflict with solved cells"));
+                }
+                b.cells[i][j] = cs;
+            }
+        }
+        for name in repr.variant {
+            let rule = Variant::from_name(&name)
+                .ok_or_else(|| D::Error::custom(format!("unknown variant: {}", name)))?;
+            b.variant = b.variant | rule;
+        }
+        Ok(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pencil_marks() {
+        let s = "
+  +---------------------------------------------------------------+
+  |   8    1246  24569 | 2347  12357  1234  | 13569 4579  1345679 |
+  | 12459   124    3   |   6   12578  1248  | 1589  45789  14579  |
+  |  1456    7    456  |  348    9    1348  |   2    458   13456  |
+  +---------------------------------------------------------------+
+  | 123469   5   2469  | 2389   2368    7   | 1689  2489   12469  |
+  | 12369  12368  269  | 2389    4      5   |   7    289   1269   |
+  | 24679  2468  24679 |   1    268   2689  | 5689    3    24569  |
+  +---------------------------------------------------------------+
+  | 23457   234    1   | 23479  237   2349  |  359    6      8    |
+  | 23467  2346    8   |   5    2367  23469 |  39     1    2379   |
+  | 23567    9   2567  | 2378  123678 12368 |   4    257   2357   |
+  +---------------------------------------------------------------+
+  ";
+        let b = Board::from_pencil_marks(s).unwrap();
+        assert_eq!("8", b.cell(Cell::new(0, 0)).to_string());
+        assert_eq!("1345679", b.cell(Cell::new(0, 8)).to_string());
+        assert_eq!("123678", b.cell(Cell::new(8, 4)).to_string());
+        // round trip by `Display`
+        assert_eq!(Some(b), Board::from_pencil_marks(&b.to_string()));
+        // a cell of no candidate is printed as 'X'
+        let mut x = b;
+        x.set_cell(Cell::new(0, 1), Candidates::from_bits(0));
+        assert!(x.to_string().contains(" X "));
+        assert_eq!(Some(x), Board::from_pencil_marks(&x.to_string()));
+
+        // HoDoKu, with labels
+        let s = "
+     1    2    3      4    5    6      7    8    9
+  .--------------.--------------.--------------.
+A | 57  457 2    | 8   347 6    | 9   1   3    |
+B | 8   45679 1  | 23457 3479 2357 | 346 3467 36 |
+C | 3   4679 46  | 47  479 1    | 468 2   5    |
+  :--------------+--------------+--------------:
+D | 6   2578 9   | 37  1   378  | 238 358 4    |
+E | 27  2478 348 | 6   5   9    | 238 38  138  |
+F | 1   458 3458 | 34  2   38   | 7   3568 9   |
+  :--------------+--------------+--------------:
+G | 4   3   568  | 1   678 578  | 68  689 2    |
+H | 259 25689 568 | 235 368 2358 | 1  34689 7   |
+I | 2   1   7    | 9   368 4    | 5   368 368  |
+  '--------------'--------------'--------------'
+";
+        let b = Board::from_pencil_marks(s).unwrap();
+        assert_eq!("45679", b.cell(Cell::new(1, 1)).to_string());
+        assert_eq!("368", b.cell(Cell::new(8, 8)).to_string());
+
+        assert_eq!(None, Board::from_pencil_marks(&s.replace("368  |", "|")));
+        assert_eq!(None, Board::from_pencil_marks(&s.replace("2358", "2X58")));
+        assert_eq!(None, Board::from_pencil_marks(&s.replace("I |", "I  ")));
+    }
+
+    #[test]
+    fn mask_parity() {
+        let mut b = Board::new(&format!("12{}", ".".repeat(79))).unwrap();
+        let marks = format!("oEeo{}", ".".repeat(77));
+        assert!(b.mask_parity(&marks));
+        assert_eq!("1", b.cell(Cell::new(0, 0)).to_string());
+        assert_eq!("2", b.cell(Cell::new(0, 1)).to_string());
+        assert_eq!("2468", b.cell(Cell::new(0, 2)).to_string());
+        assert_eq!("13579", b.cell(Cell::new(0, 3)).to_string());
+        assert_eq!("123456789", b.cell(Cell::new(0, 4)).to_string());
+
+        // the given 1 is not even
+        let b0 = b;
+        assert!(!b.mask_parity(&format!("e{}", ".".repeat(80))));
+        assert_eq!(b0, b);
+        assert!(!b.mask_parity("eo"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let givens =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        let mut b = Board::new(givens).unwrap();
+        b.set_cell(Cell::new(0, 1), Candidates::from_bits(0b101011));
+        b.set_variant(Variant::ANTI_KING);
+        let json = serde_json::to_value(b).unwrap();
+        assert_eq!(givens, json["solved"]);
+        assert_eq!("1246", json["candidates"][1]);
+        assert_eq!("123456789", json["candidates"][2]);
+        assert_eq!(serde_json::json!(["anti-king"]), json["variant"]);
+        assert_eq!(b, serde_json::from_value(json).unwrap());
+
+        let b = Board::new(givens).unwrap();
+        let json = serde_json::to_string(&b).unwrap();
+        assert!(!json.contains("variant"));
+        let only_givens = format!("{{\"givens\":\"{}\"}}", givens);
+        assert_eq!(b, serde_json::from_str(&only_givens).unwrap());
+
+        let conflict = json.replacen("\"8\"", "\"18\"", 1);
+        assert!(serde_json::from_str::<Board>(&conflict).is_err());
+        let empty = json.replacen("\"123456789\"", "\"\"", 1);
+        assert!(serde_json::from_str::<Board>(&empty).is_err());
+        let unknown = only_givens.replace("}", ",\"variant\":[\"x\"]}");
+        assert!(serde_json::from_str::<Board>(&unknown).is_err());
+
+        // solved cells of the candidates, not only the givens
+        let mut b = Board::new(givens).unwrap();
+        b.set_cell(Cell::new(0, 1), Candidates::from_bits(0b10000));
+        let json = serde_json::to_value(b).unwrap();
+        assert_eq!("85.", &json["solved"].as_str().unwrap()[..3]);
+        assert_eq!(b, serde_json::from_value(json).unwrap());
+        b.set_cell(Cell::new(0, 1), Candidates::from_bits(0));
+        assert!(serde_json::to_string(&b).is_err());
+    }
+}
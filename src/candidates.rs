@@ -16,6 +16,13 @@ impl Candidates {
 	pub fn new() -> Self {
 		Default::default()
 	}
+	/// candidates from a bitmap, bit 0 for number 1, ..., bit 8 for number 9
+	pub fn from_bits(bits: u16) -> Self {
+		Self((bits & 0x1FF) as i16)
+	}
+	pub fn bits(&self) -> u16 {
+		self.0 as u16
+	}
 
 	pub fn is_valid(&self) -> bool {
 		self.0 != 0
@@ -108,7 +115,29 @@ impl From<char> for Candidates {
 	}
 }
 
+// as a string of numbers, e.g. "1246", "" for no candidate, which is rejected
+// when reading as the solver can't handle a cell of no candidate
+#[cfg(feature = "serde")]
+impl serde::Serialize for Candidates {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let digits = self.iter().map(|c| c.lucky()).collect::<String>();
+		serializer.serialize_str(&digits)
+	}
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Candidates {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let digits = String::deserialize(deserializer)?;
+		if digits.is_empty() || !digits.chars().all(|c| ('1'..='9').contains(&c)) {
+			return Err(serde::de::Error::custom(format!("invalid candidates: {}", digits)));
+		}
+		let bits = digits.chars().fold(0, |bits, c| bits | Candidates::from(c).bits());
+		Ok(Candidates::from_bits(bits))
+	}
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
 	use super::*;
 
@@ -218,4 +247,17 @@ mod tests {
 		cs.substract(&cs4);
 		assert_eq!("X", cs.to_string());
 	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn serde() {
+		let cs = Candidates::union(Candidates::from('2'), Candidates::from('4'), Candidates::from('6'));
+		assert_eq!("\"246\"", serde_json::to_string(&cs).unwrap());
+		assert_eq!(cs, serde_json::from_str::<Candidates>("\"642\"").unwrap());
+		let mut cs = Candidates::from('1');
+		cs.pop();
+		assert_eq!("\"\"", serde_json::to_string(&cs).unwrap());
+		assert_eq!(true, serde_json::from_str::<Candidates>("\"120\"").is_err());
+		assert_eq!(true, serde_json::from_str::<Candidates>("\"\"").is_err());
+	}
 }
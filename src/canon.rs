@@ -0,0 +1,143 @@
+use crate::board::Board;
+use crate::variant::Variant;
+
+/// the canonical form of a classic puzzle, the same for all puzzles equivalent
+/// by relabelling numbers, swapping bands, rows in a band, stacks, cols in a
+/// stack, and transposing. `None` for variant rules, which aren't kept by all
+/// of them.
+///
+/// It's the smallest of all equivalent puzzles, read as 81 numbers with 0 for
+/// blanks, and with numbers relabelled in order of their first appearance.
+/// Only the givens are kept.
+pub fn canonicalize(b: &Board) -> Option<Board> {
+    if b.variant() != Variant::default() {
+        return None;
+    }
+    let mut grid = [0u8; 81];
+    for (k, cs) in b.iter().flatten().enumerate() {
+        if cs.len() == 1 {
+            grid[k] = cs.bits().trailing_zeros() as u8 + 1;
+        }
+    }
+    let perms = perms();
+    let mut best = [u8::MAX; 81];
+    for &transpose in [false, true].iter() {
+        for rows in perms.iter() {
+            for cols in perms.iter() {
+                smaller(&grid, transpose, rows, cols, &mut best);
+            }
+        }
+    }
+    let s = best
+        .iter()
+        .map(|&n| if n == 0 { '.' } else { (b'0' + n) as char })
+        .collect::<String>();
+    Board::new(&s)
+}
+
+// write the puzzle into `best` if it's smaller, exit early once it's larger
+fn smaller(
+    grid: &[u8; 81],
+    transpose: bool,
+    rows: &[usize; 9],
+    cols: &[usize; 9],
+    best: &mut [u8; 81],
+) {
+    let mut labels = [0u8; 10];
+    let mut next = 1;
+    let mut less = false;
+    for k in 0..81 {
+        let (r, c) = (rows[k / 9], cols[k % 9]);
+        let n = if transpose {
+            grid[c * 9 + r]
+        } else {
+            grid[r * 9 + c]
+        };
+        let n = if n == 0 {
+            0
+        } else {
+            if labels[n as usize] == 0 {
+                labels[n as usize] = next;
+                next += 1;
+            }
+            labels[n as usize]
+        };
+        if !less {
+            if n > best[k] {
+                return;
+            }
+            less = n < best[k];
+        }
+        if less {
+            best[k] = n;
+        }
+    }
+}
+
+// all 6^4 orders of 9 rows or cols, keeping those of a band together
+fn perms() -> Vec<[usize; 9]> {
+    const P3: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut perms = Vec::with_capacity(6 * 6 * 6 * 6);
+    for bands in P3.iter() {
+        for a in P3.iter() {
+            for b in P3.iter() {
+                for c in P3.iter() {
+                    let mut p = [0; 9];
+                    for (k, inner) in [a, b, c].iter().enumerate() {
+                        for i in 0..3 {
+                            p[k * 3 + i] = bands[k] * 3 + inner[i];
+                        }
+                    }
+                    perms.push(p);
+                }
+            }
+        }
+    }
+    perms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::Format;
+    use crate::testing::HARD;
+
+    #[test]
+    fn equivalent() {
+        let b = Board::new(HARD).unwrap();
+        let canon = canonicalize(&b).unwrap();
+        let line = Format::Line.write(&[canon]);
+        // leading blanks as many as possible, numbers from 1
+        assert!(line.starts_with("......"));
+        assert_eq!(
+            21,
+            canon.iter().flatten().filter(|cs| cs.len() == 1).count()
+        );
+        assert_eq!(Some(canon), canonicalize(&canon));
+
+        // transposed, relabelled, first two rows and last two stacks swapped
+        let s = HARD.as_bytes();
+        let mut t = String::new();
+        for row in 0..9 {
+            let row = [1, 0, 2, 3, 4, 5, 6, 7, 8][row];
+            for col in [0, 1, 2, 6, 7, 8, 3, 4, 5].iter() {
+                t.push(match s[col * 9 + row] {
+                    b'.' => '.',
+                    n => (b'1' + (n - b'1' + 4) % 9) as char,
+                });
+            }
+        }
+        assert_eq!(Some(canon), canonicalize(&Board::new(&t).unwrap()));
+
+        let mut v = b;
+        v.set_variant(Variant::from_index(1).unwrap());
+        assert_eq!(None, canonicalize(&v));
+    }
+}
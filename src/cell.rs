@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A cell of the board, row and col are 0-based
+///
+/// Printed as `r5c3` with rows and cols 1-based, or as `C5` with `{:#}`,
+/// i.e. the col letter and the row number of the README. Both are parsed,
+/// case insensitive, and `5C` as well.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cell {
+    row: u8,
+    col: u8,
+}
+impl Cell {
+    pub const fn new(row: usize, col: usize) -> Self {
+        Self {
+            row: row as u8,
+            col: col as u8,
+        }
+    }
+    /// the `k`th cell in reading order
+    pub const fn from_index(k: usize) -> Self {
+        Self::new(k / 9, k % 9)
+    }
+    /// all 81 cells in reading order
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..81).map(Self::from_index)
+    }
+    pub const fn row(self) -> usize {
+        self.row as usize
+    }
+    pub const fn col(self) -> usize {
+        self.col as usize
+    }
+    /// blocks are numbered in reading order
+    pub const fn block(self) -> usize {
+        self.row() / 3 * 3 + self.col() / 3
+    }
+    pub const fn index(self) -> usize {
+        self.row() * 9 + self.col()
+    }
+    /// the units containing the cell
+    pub fn units(self) -> [Unit; 3] {
+        [
+            Unit::Row(self.row()),
+            Unit::Col(self.col()),
+            Unit::Box(self.block()),
+        ]
+    }
+}
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{}{}", (b'A' + self.col) as char, self.row + 1)
+        } else {
+            write!(f, "r{}c{}", self.row + 1, self.col + 1)
+        }
+    }
+}
+
+/// The text isn't a cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseCellError;
+impl fmt::Display for ParseCellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid cell, expect e.g. r5c3 or C5")
+    }
+}
+impl std::error::Error for ParseCellError {}
+
+impl FromStr for Cell {
+    type Err = ParseCellError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the slicing below is by bytes
+        if !s.is_ascii() {
+            return Err(ParseCellError);
+        }
+        let s = s.to_ascii_uppercase();
+        let digit = |s: &str| match s.as_bytes() {
+            &[c @ b'1'..=b'9'] => Ok((c - b'1') as usize),
+            _ => Err(ParseCellError),
+        };
+        let letter = |s: &str| match s.as_bytes() {
+            &[c @ b'A'..=b'I'] => Ok((c - b'A') as usize),
+            _ => Err(ParseCellError),
+        };
+
+        if let Some((row, col)) = s.strip_prefix('R').and_then(|s| s.split_once('C')) {
+            return Ok(Self::new(digit(row)?, digit(col)?));
+        }
+        match s.len() {
+            2 if s.as_bytes()[0].is_ascii_digit() => {
+                Ok(Self::new(digit(&s[..1])?, letter(&s[1..])?))
+            }
+            2 => Ok(Self::new(digit(&s[1..])?, letter(&s[..1])?)),
+            _ => Err(ParseCellError),
+        }
+    }
+}
+
+/// A unit of 9 cells which can't share a digit, 0-based
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Row(usize),
+    Col(usize),
+    Box(usize),
+}
+impl Unit {
+    /// the 9 cells, in reading order
+    pub fn cells(self) -> impl Iterator<Item = Cell> {
+        (0..9).map(move |k| match self {
+            Self::Row(row) => Cell::new(row, k),
+            Self::Col(col) => Cell::new(k, col),
+            Self::Box(b) => Cell::new(b / 3 * 3 + k / 3, b % 3 * 3 + k % 3),
+        })
+    }
+}
+impl fmt::Display for Unit {
+    /// e.g. "row 5", "column 3" and "box 4", all 1-based
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Row(row) => write!(f, "row {}", row + 1),
+            Self::Col(col) => write!(f, "column {}", col + 1),
+            Self::Box(b) => write!(f, "box {}", b + 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notation() {
+        let c = Cell::new(4, 2);
+        assert_eq!("r5c3", c.to_string());
+        assert_eq!("C5", format!("{:#}", c));
+        assert_eq!(Ok(c), "r5c3".parse());
+        assert_eq!(Ok(c), "R5C3".parse());
+        assert_eq!(Ok(c), "C5".parse());
+        assert_eq!(Ok(c), "c5".parse());
+        assert_eq!(Ok(c), "5C".parse());
+        assert_eq!(Ok(Cell::new(1, 3)), "2D".parse());
+
+        for s in [
+            "r0c1", "r1c10", "r1", "J1", "A0", "A10", "AA", "", "r5c3x", "é", "Aé", "r5cé",
+        ]
+        .iter()
+        {
+            assert_eq!(Err(ParseCellError), s.parse::<Cell>(), "{}", s);
+        }
+        for c in Cell::all() {
+            assert_eq!(Ok(c), c.to_string().parse());
+            assert_eq!(Ok(c), format!("{:#}", c).parse());
+        }
+    }
+
+    #[test]
+    fn units() {
+        let c = Cell::new(4, 2);
+        assert_eq!(40 - 2, c.index());
+        assert_eq!(c, Cell::from_index(c.index()));
+        assert_eq!(3, c.block());
+        assert_eq!(
+            vec!["row 5", "column 3", "box 4"],
+            c.units().iter().map(Unit::to_string).collect::<Vec<_>>()
+        );
+        for unit in c.units().iter() {
+            assert_eq!(9, unit.cells().count());
+            assert_eq!(1, unit.cells().filter(|&x| x == c).count());
+        }
+        assert_eq!(
+            vec!["r4c4", "r4c5", "r4c6", "r5c4"],
+            Unit::Box(4)
+                .cells()
+                .take(4)
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,397 @@
+use std::fmt;
+
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::Cell;
+
+/// The constraint can't be satisfied by the board
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Contradiction;
+/// Whether any candidate is pruned
+pub type Changed = bool;
+
+/// A rule on cells beyond peers, e.g. a thermometer or house rules of your own
+///
+/// `Ripple` calls `propagate` alongside its peer ripple until nothing changes,
+/// then ripples cells solved by constraints to their peers.
+pub trait Constraint: fmt::Debug + Send + Sync {
+    /// cells `propagate` may prune, taken once when the constraint is added
+    /// to the solver
+    fn cells(&self) -> Vec<Cell>;
+    /// prune candidates which can't satisfy the constraint
+    fn propagate(&self, g: &mut Board) -> Result<Changed, Contradiction>;
+}
+
+/// keep only candidates in `cs` of the cell
+pub fn restrict(g: &mut Board, c: Cell, cs: Candidates) -> Result<Changed, Contradiction> {
+    let ca = g.cell(c);
+    let pruned = Candidates::from_bits(ca.bits() & cs.bits());
+    if !pruned.is_valid() {
+        return Err(Contradiction);
+    }
+    if pruned == ca {
+        return Ok(false);
+    }
+    g.set_cell(c, pruned);
+    Ok(true)
+}
+
+/// A row or a column of the board, 0-based
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Line {
+    Row(usize),
+    Col(usize),
+}
+impl Line {
+    fn cell(self, k: usize) -> Cell {
+        match self {
+            Self::Row(row) => Cell::new(row, k),
+            Self::Col(col) => Cell::new(k, col),
+        }
+    }
+}
+
+/// Strictly increasing from the bulb along the path
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thermo(Vec<Cell>);
+impl Thermo {
+    /// `path` begins with the bulb, neighbouring cells are orthogonally or
+    /// diagonally adjacent, and no cell is on it twice
+    pub fn new(path: &[Cell]) -> Option<Self> {
+        if path.len() < 2 || path.len() > 9 || !is_path(path) {
+            return None;
+        }
+        Some(Self(path.to_vec()))
+    }
+    /// the bulb first
+    pub fn path(&self) -> &[Cell] {
+        &self.0
+    }
+}
+impl Constraint for Thermo {
+    fn cells(&self) -> Vec<Cell> {
+        self.0.clone()
+    }
+    fn propagate(&self, g: &mut Board) -> Result<Changed, Contradiction> {
+        let path = &self.0;
+        if path.iter().any(|&c| cell(g, c) == 0) {
+            return Err(Contradiction);
+        }
+        let mut changed = false;
+        // greater than the min of the previous one
+        for w in path.windows(2) {
+            let lo = min(cell(g, w[0])) + 1;
+            changed |= keep(g, w[1], range(lo, 9))?;
+        }
+        // less than the max of the next one
+        for w in path.windows(2).rev() {
+            let hi = max(cell(g, w[1])) - 1;
+            changed |= keep(g, w[0], range(1, hi))?;
+        }
+        Ok(changed)
+    }
+}
+
+/// The circle equals the sum along the arrow, numbers may repeat on the arrow
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Arrow {
+    circle: Cell,
+    path: Vec<Cell>,
+}
+impl Arrow {
+    /// the arrow begins with the cell next to the circle, of at most 9 cells
+    /// as each adds at least 1 to the sum, and neither crosses itself nor the
+    /// circle
+    pub fn new(circle: Cell, path: &[Cell]) -> Option<Self> {
+        let mut cells = vec![circle];
+        cells.extend_from_slice(path);
+        if path.is_empty() || path.len() > 9 || !is_path(&cells) {
+            return None;
+        }
+        Some(Self {
+            circle,
+            path: path.to_vec(),
+        })
+    }
+    pub fn circle(&self) -> Cell {
+        self.circle
+    }
+    pub fn path(&self) -> &[Cell] {
+        &self.path
+    }
+}
+impl Constraint for Arrow {
+    fn cells(&self) -> Vec<Cell> {
+        let mut cells = self.path.clone();
+        cells.push(self.circle);
+        cells
+    }
+    fn propagate(&self, g: &mut Board) -> Result<Changed, Contradiction> {
+        let (circle, path) = (self.circle, &self.path);
+        if cell(g, circle) == 0 || path.iter().any(|&c| cell(g, c) == 0) {
+            return Err(Contradiction);
+        }
+        let min_sum: u8 = path.iter().map(|&c| min(cell(g, c))).sum();
+        let max_sum: u8 = path.iter().map(|&c| max(cell(g, c))).sum();
+        let mut changed = keep(g, circle, range(min_sum, max_sum))?;
+
+        let (lo, hi) = (min(cell(g, circle)), max(cell(g, circle)));
+        for &c in path {
+            let (c_min, c_max) = (min(cell(g, c)), max(cell(g, c)));
+            // bounds of the sum of the others
+            let (others_min, others_max) = (min_sum - c_min, max_sum - c_max);
+            let mask = range(lo.saturating_sub(others_max), hi.saturating_sub(others_min));
+            changed |= keep(g, c, mask)?;
+        }
+        Ok(changed)
+    }
+}
+
+/// The sum of numbers between 1 and 9 in the line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sandwich {
+    line: Line,
+    sum: u8,
+}
+impl Sandwich {
+    pub fn new(line: Line, sum: u8) -> Option<Self> {
+        let k = match line {
+            Line::Row(k) | Line::Col(k) => k,
+        };
+        // 2+3+...+8
+        if k > 8 || sum > 35 {
+            return None;
+        }
+        Some(Self { line, sum })
+    }
+    pub fn line(&self) -> Line {
+        self.line
+    }
+    pub fn sum(&self) -> u8 {
+        self.sum
+    }
+}
+impl Constraint for Sandwich {
+    fn cells(&self) -> Vec<Cell> {
+        (0..9).map(|k| self.line.cell(k)).collect()
+    }
+    fn propagate(&self, g: &mut Board) -> Result<Changed, Contradiction> {
+        let (line, sum) = (self.line, self.sum);
+        const ONE: u16 = 1;
+        const NINE: u16 = 1 << 8;
+        // 2-8 as bits 1-7
+        const CRUST: u16 = 0x1FF & !(ONE | NINE);
+
+        let cells = (0..9).map(|k| cell(g, line.cell(k))).collect::<Vec<_>>();
+        // numbers allowed by all possible fillings
+        let mut allowed = [0u16; 9];
+        for i in 0..9 {
+            for j in i + 1..9 {
+                for &(bi, bj) in [(ONE, NINE), (NINE, ONE)].iter() {
+                    if cells[i] & bi == 0 || cells[j] & bj == 0 {
+                        continue;
+                    }
+                    // numbers between 1 and 9, as a subset of 2-8
+                    for sub in 0..(1u16 << 7) {
+                        let inner = sub << 1;
+                        if inner.count_ones() as usize != j - i - 1 || sum_of(inner) != sum {
+                            continue;
+                        }
+                        let outer = CRUST & !inner;
+                        let fits = (0..9).all(|k| {
+                            k == i
+                                || k == j
+                                || (i < k && k < j && cells[k] & inner != 0)
+                                || ((k < i || j < k) && cells[k] & outer != 0)
+                        });
+                        if !fits {
+                            continue;
+                        }
+                        for (k, a) in allowed.iter_mut().enumerate() {
+                            *a |= if k == i {
+                                bi
+                            } else if k == j {
+                                bj
+                            } else if i < k && k < j {
+                                inner
+                            } else {
+                                outer
+                            };
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut changed = false;
+        for (k, &a) in allowed.iter().enumerate() {
+            changed |= keep(g, line.cell(k), a)?;
+        }
+        Ok(changed)
+    }
+}
+
+// keep only candidates in `bits` of the cell
+fn keep(g: &mut Board, c: Cell, bits: u16) -> Result<Changed, Contradiction> {
+    restrict(g, c, Candidates::from_bits(bits))
+}
+fn cell(g: &Board, c: Cell) -> u16 {
+    g.cell(c).bits()
+}
+// min number of the candidates bits
+fn min(bits: u16) -> u8 {
+    bits.trailing_zeros() as u8 + 1
+}
+// max number of the candidates bits
+fn max(bits: u16) -> u8 {
+    16 - bits.leading_zeros() as u8
+}
+// numbers in [lo, hi] as bits
+fn range(lo: u8, hi: u8) -> u16 {
+    let (lo, hi) = (lo.max(1), hi.min(9));
+    if lo > hi {
+        0
+    } else {
+        ((1u16 << hi) - 1) & !((1u16 << (lo - 1)) - 1)
+    }
+}
+fn sum_of(bits: u16) -> u8 {
+    (0..9).filter(|i| bits & (1 << i) != 0).map(|i| i + 1).sum()
+}
+
+// cells are in the board, each one once, and each one touches the next one
+fn is_path(cells: &[Cell]) -> bool {
+    cells.iter().all(|c| c.row() < 9 && c.col() < 9)
+        && cells
+            .iter()
+            .enumerate()
+            .all(|(i, c)| !cells[..i].contains(c))
+        && cells.windows(2).all(|w| {
+            let (dr, dc) = (
+                w[0].row().max(w[1].row()) - w[0].row().min(w[1].row()),
+                w[0].col().max(w[1].col()) - w[0].col().min(w[1].col()),
+            );
+            dr <= 1 && dc <= 1 && dr + dc > 0
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty() -> Board {
+        Board::new(&".".repeat(81)).unwrap()
+    }
+    fn cells(path: &[(usize, usize)]) -> Vec<Cell> {
+        path.iter().map(|&(r, c)| Cell::new(r, c)).collect()
+    }
+
+    #[test]
+    fn bits() {
+        assert_eq!("3456", Candidates::from_bits(range(3, 6)).to_string());
+        assert_eq!("X", Candidates::from_bits(range(6, 3)).to_string());
+        assert_eq!("123456789", Candidates::from_bits(range(0, 12)).to_string());
+        assert_eq!((2, 7), (min(0b1000010), max(0b1000010)));
+        assert_eq!(18, sum_of(0b1110000));
+    }
+
+    #[test]
+    fn thermo() {
+        assert_eq!(None, Thermo::new(&cells(&[(0, 0)])));
+        assert_eq!(None, Thermo::new(&cells(&[(0, 0), (0, 2)])));
+        assert_eq!(None, Thermo::new(&cells(&[(0, 0), (1, 1), (0, 0)])));
+
+        let mut g = empty();
+        let t = Thermo::new(&cells(&[(0, 0), (1, 1), (1, 2), (2, 2)])).unwrap();
+        assert_eq!(Ok(true), t.propagate(&mut g));
+        assert_eq!("123456", g.cell(Cell::new(0, 0)).to_string());
+        assert_eq!("234567", g.cell(Cell::new(1, 1)).to_string());
+        assert_eq!("456789", g.cell(Cell::new(2, 2)).to_string());
+        assert_eq!(Ok(false), t.propagate(&mut g));
+
+        g.set_cell(Cell::new(1, 2), Candidates::from('8'));
+        assert_eq!(Ok(true), t.propagate(&mut g));
+        assert_eq!("9", g.cell(Cell::new(2, 2)).to_string());
+        assert_eq!("234567", g.cell(Cell::new(1, 1)).to_string());
+
+        g.set_cell(Cell::new(0, 0), Candidates::from('8'));
+        assert_eq!(Err(Contradiction), t.propagate(&mut g));
+
+        // a cell of no candidate, e.g. an 'X' of a pencil-mark grid
+        let mut g = empty();
+        g.set_cell(Cell::new(1, 2), Candidates::from_bits(0));
+        assert_eq!(Err(Contradiction), t.propagate(&mut g));
+    }
+
+    #[test]
+    fn arrow() {
+        assert_eq!(None, Arrow::new(Cell::new(0, 0), &cells(&[])));
+        assert_eq!(None, Arrow::new(Cell::new(0, 0), &cells(&[(2, 2)])));
+        // back to the circle, or over a cell twice
+        assert_eq!(None, Arrow::new(Cell::new(0, 0), &cells(&[(1, 1), (0, 0)])));
+        assert_eq!(
+            None,
+            Arrow::new(Cell::new(0, 0), &cells(&[(0, 1), (1, 1), (0, 1)]))
+        );
+        // a snake of 10 cells sums to 10 at least, and 29 of 9s overflow u8
+        let long = (1..36)
+            .map(|k| {
+                let (row, col) = (k / 9, k % 9);
+                (row, if row % 2 == 0 { col } else { 8 - col })
+            })
+            .collect::<Vec<_>>();
+        let circle = Cell::new(0, 0);
+        assert!(Arrow::new(circle, &cells(&long[..9])).is_some());
+        assert_eq!(None, Arrow::new(circle, &cells(&long[..10])));
+        assert_eq!(None, Arrow::new(circle, &cells(&long)));
+
+        let mut g = empty();
+        let a = Arrow::new(Cell::new(0, 0), &cells(&[(1, 1), (2, 2)])).unwrap();
+        assert_eq!(Ok(true), a.propagate(&mut g));
+        assert_eq!("23456789", g.cell(Cell::new(0, 0)).to_string());
+        assert_eq!("12345678", g.cell(Cell::new(1, 1)).to_string());
+
+        g.set_cell(Cell::new(0, 0), Candidates::from('4'));
+        g.set_cell(Cell::new(1, 1), Candidates::from('3'));
+        assert_eq!(Ok(true), a.propagate(&mut g));
+        assert_eq!("1", g.cell(Cell::new(2, 2)).to_string());
+
+        g.set_cell(Cell::new(2, 2), Candidates::from('2'));
+        assert_eq!(Err(Contradiction), a.propagate(&mut g));
+
+        let mut g = empty();
+        g.set_cell(Cell::new(0, 0), Candidates::from_bits(0));
+        assert_eq!(Err(Contradiction), a.propagate(&mut g));
+    }
+
+    #[test]
+    fn sandwich() {
+        assert_eq!(None, Sandwich::new(Line::Row(9), 0));
+        assert_eq!(None, Sandwich::new(Line::Row(0), 36));
+
+        // 35 means all of 2-8 are between, so 1 and 9 at both ends
+        let mut g = empty();
+        let s = Sandwich::new(Line::Col(3), 35).unwrap();
+        assert_eq!(Ok(true), s.propagate(&mut g));
+        assert_eq!("19", g.cell(Cell::new(0, 3)).to_string());
+        assert_eq!("2345678", g.cell(Cell::new(4, 3)).to_string());
+        assert_eq!("19", g.cell(Cell::new(8, 3)).to_string());
+
+        // 1 and 9 are next to each other
+        let mut g = empty();
+        g.set_cell(Cell::new(0, 4), Candidates::from('1'));
+        let s = Sandwich::new(Line::Row(0), 0).unwrap();
+        assert_eq!(Ok(true), s.propagate(&mut g));
+        assert_eq!("23456789", g.cell(Cell::new(0, 3)).to_string());
+        assert_eq!("2345678", g.cell(Cell::new(0, 2)).to_string());
+        g.set_cell(Cell::new(0, 3), Candidates::from('5'));
+        assert_eq!(Ok(true), s.propagate(&mut g));
+        assert_eq!("9", g.cell(Cell::new(0, 5)).to_string());
+
+        let mut g = empty();
+        g.set_cell(Cell::new(0, 0), Candidates::from('1'));
+        g.set_cell(Cell::new(0, 2), Candidates::from('9'));
+        let s = Sandwich::new(Line::Row(0), 9).unwrap();
+        assert_eq!(Err(Contradiction), s.propagate(&mut g));
+    }
+}
@@ -0,0 +1,308 @@
+use std::path::Path;
+
+use crate::board::Board;
+
+/// Formats of puzzle files
+///
+/// - `Sdk`: SadMan Software Sudoku, a puzzle as 9 lines, '.' for blanks,
+///   lines beginning with '#' are headers, e.g. `#AAuthor`
+/// - `Sdm`: SadMan Software Sudoku collection, one puzzle per line, '0' for blanks
+/// - `Ss`: Simple Sudoku, a puzzle as 9 lines with blocks split by '|' and
+///   '-', '.' for blanks
+/// - `Line`: one puzzle per line, '0' or '.' for blanks, lines beginning with
+///   '#' and text after the puzzle are comments
+///
+/// A file of `Sdk` or `Ss` holds one puzzle, more of them are split by an empty
+/// line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Sdk,
+    Sdm,
+    Ss,
+    Line,
+}
+impl Format {
+    /// guess the format by the extension of the file, `Line` by default
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("sdk") => Self::Sdk,
+            Some(ext) if ext.eq_ignore_ascii_case("sdm") => Self::Sdm,
+            Some(ext) if ext.eq_ignore_ascii_case("ss") => Self::Ss,
+            _ => Self::Line,
+        }
+    }
+
+    /// all puzzles of the file, or the number of the first invalid line,
+    /// from 1, e.g. the first line of a puzzle missing cells
+    pub fn read(self, s: &str) -> Result<Vec<Board>, usize> {
+        match self {
+            Self::Sdk => read_sdk(s),
+            Self::Ss => read_ss(s),
+            Self::Sdm | Self::Line => (1..)
+                .zip(s.lines())
+                .filter(|(_, line)| !is_comment(line))
+                .map(|(k, line)| parse_line(line).ok_or(k))
+                .collect(),
+        }
+    }
+    /// givens of the puzzles in the format
+    pub fn write(self, puzzles: &[Board]) -> String {
+        let mut s = String::with_capacity(puzzles.len() * 82);
+        for (k, b) in puzzles.iter().enumerate() {
+            match self {
+                Self::Sdk | Self::Ss => {
+                    if k > 0 {
+                        s.push('\n');
+                    }
+                    s.push_str(&self.write_grid(b));
+                }
+                Self::Sdm => s.push_str(&givens(b, '0')),
+                Self::Line => s.push_str(&givens(b, '.')),
+            }
+            if let Self::Sdm | Self::Line = self {
+                s.push('\n');
+            }
+        }
+        s
+    }
+    fn write_grid(self, b: &Board) -> String {
+        let line = givens(b, '.');
+        let mut s = String::with_capacity(9 * 12 + 12);
+        for (i, row) in line.as_bytes().chunks(9).enumerate() {
+            let row = std::str::from_utf8(row).unwrap();
+            if self == Self::Sdk {
+                s.push_str(row);
+            } else {
+                if i > 0 && i % 3 == 0 {
+                    s.push_str("-----------\n");
+                }
+                s.push_str(&format!("{}|{}|{}", &row[0..3], &row[3..6], &row[6..9]));
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
+
+/// one puzzle of 81 cells at the beginning of the line, '0' or '.' for
+/// blanks, text after it is a comment
+pub fn parse_line(line: &str) -> Option<Board> {
+    let line = line.trim_start();
+    let cells = line.get(..81)?;
+    if !cells.chars().all(is_cell) {
+        return None;
+    }
+    // the puzzle must be followed by a separator
+    match line[81..].chars().next() {
+        Some(c) if !c.is_whitespace() && !"#;,|".contains(c) => return None,
+        _ => {}
+    }
+    Board::new(&cells.replace('0', "."))
+}
+/// empty lines and lines beginning with '#'
+pub fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+fn read_sdk(s: &str) -> Result<Vec<Board>, usize> {
+    let mut grids = Grids::default();
+    // only puzzles are read, not saved states, up to the next empty line
+    let mut skip = false;
+    for (k, line) in (1..).zip(s.lines().map(str::trim)) {
+        if line.is_empty() {
+            skip = false;
+            grids.end()?;
+        } else if line.starts_with('[') {
+            skip = !line.eq_ignore_ascii_case("[puzzle]");
+        } else if !skip && !line.starts_with('#') {
+            if !line.chars().all(is_cell) {
+                return Err(k);
+            }
+            line.chars().try_for_each(|c| grids.push(k, c))?;
+        }
+    }
+    grids.finish()
+}
+fn read_ss(s: &str) -> Result<Vec<Board>, usize> {
+    let mut grids = Grids::default();
+    for (k, line) in (1..).zip(s.lines()) {
+        if line.trim().is_empty() {
+            grids.end()?;
+        }
+        for c in line.chars() {
+            match c {
+                '1'..='9' | '.' => grids.push(k, c)?,
+                '0' | 'X' | 'x' => grids.push(k, '.')?,
+                '|' | '-' | '+' | '*' => {}
+                c if c.is_whitespace() => {}
+                _ => return Err(k),
+            }
+        }
+    }
+    grids.finish()
+}
+
+// puzzles of 81 cells each read cell by cell, split by empty lines
+#[derive(Default)]
+struct Grids {
+    boards: Vec<Board>,
+    cells: String,
+    // the line the cells begin at
+    line: usize,
+}
+impl Grids {
+    fn push(&mut self, line: usize, c: char) -> Result<(), usize> {
+        if self.cells.is_empty() {
+            self.line = line;
+        }
+        if self.cells.len() == 81 {
+            return Err(line);
+        }
+        self.cells.push(if c == '0' { '.' } else { c });
+        Ok(())
+    }
+    // the end of a puzzle, if any cells are read
+    fn end(&mut self) -> Result<(), usize> {
+        if self.cells.is_empty() {
+            return Ok(());
+        }
+        if self.cells.len() != 81 {
+            return Err(self.line);
+        }
+        self.boards.push(Board::new(&self.cells).ok_or(self.line)?);
+        self.cells.clear();
+        Ok(())
+    }
+    fn finish(mut self) -> Result<Vec<Board>, usize> {
+        self.end()?;
+        Ok(self.boards)
+    }
+}
+
+fn is_cell(c: char) -> bool {
+    c.is_ascii_digit() || c == '.'
+}
+// solved cells as numbers, others as `blank`
+fn givens(b: &Board, blank: char) -> String {
+    b.iter()
+        .flatten()
+        .map(|cs| if cs.len() == 1 { cs.lucky() } else { blank })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HARD;
+
+    #[test]
+    fn from_path() {
+        assert_eq!(Format::Sdk, Format::from_path(Path::new("a/b.SDK")));
+        assert_eq!(Format::Sdm, Format::from_path(Path::new("b.sdm")));
+        assert_eq!(Format::Ss, Format::from_path(Path::new("b.ss")));
+        assert_eq!(Format::Line, Format::from_path(Path::new("b.txt")));
+        assert_eq!(Format::Line, Format::from_path(Path::new("b")));
+    }
+
+    #[test]
+    fn line() {
+        let b = Board::new(HARD).unwrap();
+        assert_eq!(Some(b), parse_line(HARD));
+        assert_eq!(Some(b), parse_line(&HARD.replace('.', "0")));
+        assert_eq!(
+            Some(b),
+            parse_line(&format!("  {} # hard, 11 givens", HARD))
+        );
+        assert_eq!(Some(b), parse_line(&format!("{};rating 10.5", HARD)));
+        assert_eq!(None, parse_line(&HARD[1..]));
+        assert_eq!(None, parse_line(&format!("{}1", HARD)));
+        assert_eq!(None, parse_line(&HARD.replace('8', "x")));
+
+        let s = format!("# puzzles\n{}\n\n{}  easy?\n", HARD, HARD.replace('.', "0"));
+        assert_eq!(Ok(vec![b, b]), Format::Line.read(&s));
+        assert_eq!(Err(6), Format::Line.read(&format!("{}\n{}", s, &HARD[1..])));
+        assert_eq!(format!("{}\n{}\n", HARD, HARD), Format::Line.write(&[b, b]));
+    }
+
+    #[test]
+    fn sdm() {
+        let b = Board::new(HARD).unwrap();
+        let s = Format::Sdm.write(&[b, b]);
+        assert_eq!(format!("{0}\n{0}\n", HARD.replace('.', "0")), s);
+        assert_eq!(Ok(vec![b, b]), Format::Sdm.read(&s));
+    }
+
+    #[test]
+    fn sdk() {
+        let s = "
+#Aanonymous
+#DThe hardest?
+[Puzzle]
+8........
+..36.....
+.7..9.2..
+.5...7...
+....457..
+...1...3.
+..1....68
+..85...1.
+.9....4..
+[State]
+812753649
+";
+        let b = Board::new(HARD).unwrap();
+        assert_eq!(Ok(vec![b]), Format::Sdk.read(s));
+        assert_eq!(Ok(vec![b]), Format::Sdk.read(&Format::Sdk.write(&[b])));
+        assert_eq!(Err(14), Format::Sdk.read(&s.replace("[State]\n", "")));
+        assert_eq!("8........\n..36.....\n", &Format::Sdk.write(&[b])[..20]);
+
+        // puzzles split by an empty line, the state of each isn't read
+        let e = Board::new(&HARD.replacen('.', "1", 1)).unwrap();
+        assert_eq!(
+            Ok(vec![b, e]),
+            Format::Sdk.read(&Format::Sdk.write(&[b, e]))
+        );
+        let two = format!("{}\n{}", s, s.replacen("8........", "81.......", 1));
+        assert_eq!(Ok(vec![b, e]), Format::Sdk.read(&two));
+        assert_eq!(Err(5), Format::Sdk.read(&s.replace(".9....4..\n", "")));
+        // a short puzzle doesn't take cells of the next one
+        let short = two.replacen(".9....4..\n", "", 1);
+        assert_eq!(Err(5), Format::Sdk.read(&short));
+    }
+
+    #[test]
+    fn ss() {
+        let s = "
+*-----------*
+|8..|...|...|
+|..3|6..|...|
+|.7.|.9.|2..|
+|---+---+---|
+|.5.|..7|...|
+|...|.45|7..|
+|...|1..|.3.|
+|---+---+---|
+|..1|...|.68|
+|..8|5..|.1.|
+|.9.|...|4..|
+*-----------*
+";
+        let b = Board::new(HARD).unwrap();
+        assert_eq!(Ok(vec![b]), Format::Ss.read(s));
+        let written = Format::Ss.write(&[b]);
+        assert_eq!("8..|...|...", written.lines().next().unwrap());
+        assert_eq!("-----------", written.lines().nth(3).unwrap());
+        assert_eq!(Ok(vec![b]), Format::Ss.read(&written));
+        assert_eq!(Err(5), Format::Ss.read(&s.replace("|.9.|", "|.9?|")));
+
+        let e = Board::new(&HARD.replacen('.', "1", 1)).unwrap();
+        assert_eq!(Ok(vec![b, e]), Format::Ss.read(&Format::Ss.write(&[b, e])));
+        assert_eq!(Err(3), Format::Ss.read(&s.replace("|.9.|", "|.9|")));
+        let short = Format::Ss
+            .write(&[b, e])
+            .replacen(".9.|...|4..", ".9.|...|4.", 1);
+        assert_eq!(Err(1), Format::Ss.read(&short));
+        assert_eq!(Ok(vec![]), Format::Ss.read(""));
+    }
+}
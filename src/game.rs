@@ -0,0 +1,274 @@
+use std::time::Duration;
+
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::Cell;
+use crate::hint::{bit, number};
+use crate::ripple::Ripple;
+
+pub mod save;
+
+/// What the player does to a cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// enter a number 1-9
+    Enter(u8),
+    /// erase the number, or pencil marks if there's no number
+    Clear,
+    /// toggle a pencil mark 1-9
+    Mark(u8),
+}
+
+/// A move of the log, with the cell before it for undoing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub cell: Cell,
+    pub action: Action,
+    /// number entered before the move
+    pub entry: Option<u8>,
+    /// pencil marks before the move
+    pub marks: Candidates,
+}
+
+/// A game of a puzzle, givens are kept apart from what the player enters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    givens: Board,
+    solution: Board,
+    entries: [Option<u8>; 81],
+    marks: [Candidates; 81],
+    moves: Vec<Move>,
+    // moves applied, the ones after it are undone ones to redo
+    applied: usize,
+    elapsed: Duration,
+}
+impl Session {
+    /// a new game, `None` if the puzzle hasn't exactly one answer, as
+    /// entries are told mistakes by it
+    pub fn new(givens: Board) -> Option<Self> {
+        let mut r = Ripple::from_board(givens);
+        // the answer is kept as the board
+        if r.count(2) != 1 {
+            return None;
+        }
+        Some(Self {
+            givens,
+            solution: *r.board(),
+            entries: [None; 81],
+            marks: [Candidates::from_bits(0); 81],
+            moves: Vec::new(),
+            applied: 0,
+            elapsed: Duration::default(),
+        })
+    }
+
+    pub fn givens(&self) -> &Board {
+        &self.givens
+    }
+    pub fn solution(&self) -> &Board {
+        &self.solution
+    }
+    pub fn is_given(&self, c: Cell) -> bool {
+        self.givens.cell(c).len() == 1
+    }
+    /// the number entered by the player
+    pub fn entry(&self, c: Cell) -> Option<u8> {
+        self.entries[c.index()]
+    }
+    pub fn marks(&self, c: Cell) -> Candidates {
+        self.marks[c.index()]
+    }
+    /// time played, kept by the front-end
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+    /// the given or entered number
+    pub fn number(&self, c: Cell) -> Option<u8> {
+        if self.is_given(c) {
+            Some(number(self.givens.cell(c)))
+        } else {
+            self.entry(c)
+        }
+    }
+    /// givens and entries as solved cells, others with all candidates
+    pub fn board(&self) -> Board {
+        let mut b = self.givens;
+        for c in Cell::all() {
+            if let Some(n) = self.entry(c) {
+                b.set_cell(c, bit(n));
+            }
+        }
+        b
+    }
+
+    /// the number is the same as a peer's
+    pub fn is_conflict(&self, c: Cell) -> bool {
+        match self.number(c) {
+            Some(n) => self
+                .givens
+                .peers_of(c)
+                .iter()
+                .any(|&p| self.number(p) == Some(n)),
+            None => false,
+        }
+    }
+    /// the entry isn't the number of the solution
+    pub fn is_mistake(&self, c: Cell) -> bool {
+        matches!(self.entry(c), Some(n) if n != number(self.solution.cell(c)))
+    }
+    /// cells of wrong entries, in reading order
+    pub fn mistakes(&self) -> Vec<Cell> {
+        Cell::all().filter(|&c| self.is_mistake(c)).collect()
+    }
+    /// all cells are filled as the solution
+    pub fn is_complete(&self) -> bool {
+        Cell::all().all(|c| self.number(c) == Some(number(self.solution.cell(c))))
+    }
+
+    /// make a move, false if the cell is a given, the number isn't 1-9, or
+    /// nothing changes
+    pub fn play(&mut self, cell: Cell, action: Action) -> bool {
+        let m = Move {
+            cell,
+            action,
+            entry: self.entry(cell),
+            marks: self.marks(cell),
+        };
+        match action {
+            Action::Enter(n) | Action::Mark(n) if !(1..=9).contains(&n) => return false,
+            _ if self.is_given(cell) => return false,
+            _ => {}
+        }
+        self.apply(&m);
+        if self.entry(cell) == m.entry && self.marks(cell) == m.marks {
+            return false;
+        }
+        self.moves.truncate(self.applied);
+        self.moves.push(m);
+        self.applied += 1;
+        true
+    }
+    pub fn enter(&mut self, c: Cell, n: u8) -> bool {
+        self.play(c, Action::Enter(n))
+    }
+    pub fn clear(&mut self, c: Cell) -> bool {
+        self.play(c, Action::Clear)
+    }
+    pub fn toggle_mark(&mut self, c: Cell, n: u8) -> bool {
+        self.play(c, Action::Mark(n))
+    }
+    /// undo the last move, `None` if there's none
+    pub fn undo(&mut self) -> Option<Move> {
+        let m = *self.moves[..self.applied].last()?;
+        self.entries[m.cell.index()] = m.entry;
+        self.marks[m.cell.index()] = m.marks;
+        self.applied -= 1;
+        Some(m)
+    }
+    /// redo the last move undone, `None` if there's none
+    pub fn redo(&mut self) -> Option<Move> {
+        let m = *self.moves.get(self.applied)?;
+        self.apply(&m);
+        self.applied += 1;
+        Some(m)
+    }
+    /// moves made, without the undone ones
+    pub fn moves(&self) -> &[Move] {
+        &self.moves[..self.applied]
+    }
+    /// moves undone, to redo from the first one
+    pub fn undone(&self) -> &[Move] {
+        &self.moves[self.applied..]
+    }
+
+    fn apply(&mut self, m: &Move) {
+        let k = m.cell.index();
+        match m.action {
+            Action::Enter(n) => self.entries[k] = Some(n),
+            Action::Clear if self.entries[k].is_some() => self.entries[k] = None,
+            Action::Clear => self.marks[k] = Candidates::from_bits(0),
+            Action::Mark(n) => {
+                self.marks[k] = Candidates::from_bits(self.marks[k].bits() ^ bit(n).bits())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{ANSWER, HARD};
+
+    #[test]
+    fn moves() {
+        let mut s = Session::new(Board::new(HARD).unwrap()).unwrap();
+        let (a, b) = (Cell::new(0, 1), Cell::new(0, 2));
+        assert!(!s.enter(Cell::new(0, 0), 1));
+        assert!(!s.enter(a, 0));
+        assert!(s.toggle_mark(a, 1));
+        assert!(s.toggle_mark(a, 4));
+        assert_eq!(0b1001, s.marks(a).bits());
+        assert!(s.enter(a, 8));
+        assert!(!s.enter(a, 8));
+        assert_eq!(Some(8), s.number(a));
+        assert!(s.is_conflict(a));
+        assert_eq!(vec![a], s.mistakes());
+        // the number first, then the marks
+        assert!(s.clear(a));
+        assert_eq!(0b1001, s.marks(a).bits());
+        assert!(s.clear(a));
+        assert_eq!(0, s.marks(a).bits());
+        assert!(!s.clear(a));
+        assert_eq!(5, s.moves().len());
+
+        assert_eq!(Action::Clear, s.undo().unwrap().action);
+        assert_eq!(Action::Clear, s.undo().unwrap().action);
+        assert_eq!(Some(8), s.entry(a));
+        assert_eq!(2, s.undone().len());
+        assert_eq!(Action::Clear, s.redo().unwrap().action);
+        assert_eq!(None, s.entry(a));
+        // a new move drops the undone ones
+        assert!(s.enter(b, 2));
+        assert_eq!(None, s.redo());
+        assert!(s.undone().is_empty());
+        assert_eq!(5, s.moves().len());
+        while s.undo().is_some() {}
+        assert_eq!(
+            Session::new(Board::new(HARD).unwrap()).unwrap().board(),
+            s.board()
+        );
+        assert_eq!(5, s.undone().len());
+    }
+
+    #[test]
+    fn complete() {
+        assert_eq!(
+            None,
+            Session::new(Board::new(&"88".repeat(41)[..81]).unwrap())
+        );
+        // more than one answer, to tell mistakes by
+        assert_eq!(None, Session::new(Board::new(&".".repeat(81)).unwrap()));
+
+        let mut s = Session::new(Board::new(HARD).unwrap()).unwrap();
+        assert_eq!(Board::new(ANSWER).unwrap(), *s.solution());
+        for (c, n) in Cell::all().zip(ANSWER.bytes()) {
+            assert!(!s.is_complete());
+            if !s.is_given(c) {
+                s.enter(c, n - b'0');
+            }
+        }
+        assert!(s.is_complete());
+        assert!(s.mistakes().is_empty());
+        assert_eq!(
+            ANSWER,
+            s.board()
+                .iter()
+                .flatten()
+                .map(|cs| cs.lucky())
+                .collect::<String>()
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use super::{Action, Session};
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::Cell;
+use crate::formats::Format;
+use crate::variant::Variant;
+
+/// version of the save format written
+pub const VERSION: u32 = 1;
+
+impl Session {
+    /// the game as text of the current version, a field per line:
+    /// ```text
+    /// sudoku-save 1
+    /// givens 8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..
+    /// variant anti-knight
+    /// elapsed 125
+    /// entries .8...............................................................................
+    /// marks - - 14 - ...
+    /// moves r1c3m1 r1c3m4 r1c2=8 r1c2x
+    /// undone 1
+    /// ```
+    /// `entries` has 81 numbers, '.' for none, and `marks` has 81 groups of
+    /// pencil marks, '-' for none. `moves` is the log in order, `=` to enter,
+    /// `m` to toggle a mark and `x` to clear, with the last `undone` of them
+    /// undone. `variant` is omitted for classic rules.
+    pub fn save(&self) -> String {
+        let mut s = format!("sudoku-save {}\n", VERSION);
+        let _ = writeln!(s, "givens {}", givens(&self.givens));
+        let names = self.givens.variant().names();
+        if !names.is_empty() {
+            let _ = writeln!(s, "variant {}", names.join(" "));
+        }
+        let _ = writeln!(s, "elapsed {}", self.elapsed.as_secs());
+        let entries = self
+            .entries
+            .iter()
+            .map(|e| e.map_or('.', |n| (b'0' + n) as char))
+            .collect::<String>();
+        let _ = writeln!(s, "entries {}", entries);
+        let marks = self
+            .marks
+            .iter()
+            .map(|cs| {
+                if cs.is_empty() {
+                    "-".to_string()
+                } else {
+                    cs.to_string()
+                }
+            })
+            .collect::<Vec<_>>();
+        let _ = writeln!(s, "marks {}", marks.join(" "));
+        let moves = self
+            .moves
+            .iter()
+            .map(|m| match m.action {
+                Action::Enter(n) => format!("{}={}", m.cell, n),
+                Action::Mark(n) => format!("{}m{}", m.cell, n),
+                Action::Clear => format!("{}x", m.cell),
+            })
+            .collect::<Vec<_>>();
+        let _ = writeln!(s, "moves {}", moves.join(" "));
+        let _ = writeln!(s, "undone {}", self.undone().len());
+        s
+    }
+
+    /// a saved game, `None` if it's invalid, of a newer version, or the moves
+    /// don't lead to the numbers and marks
+    pub fn load(s: &str) -> Option<Self> {
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        let version = lines
+            .next()?
+            .strip_prefix("sudoku-save ")?
+            .parse::<u32>()
+            .ok()?;
+        // older versions are read here as they come, with their fields mapped
+        // to the current ones
+        if !(1..=VERSION).contains(&version) {
+            return None;
+        }
+        let mut save = Fields::default();
+        for line in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            match key {
+                "givens" => save.givens = Some(value),
+                "entries" => save.entries = Some(value),
+                "marks" => save.marks = Some(value),
+                "variant" => save.variant = value,
+                "elapsed" => save.elapsed = value.parse().ok()?,
+                "moves" => save.moves = value,
+                "undone" => save.undone = value.parse().ok()?,
+                _ => return None,
+            }
+        }
+        save.session()
+    }
+}
+
+// fields of a save, `variant` is empty for classic rules
+#[derive(Default)]
+struct Fields<'a> {
+    givens: Option<&'a str>,
+    entries: Option<&'a str>,
+    marks: Option<&'a str>,
+    variant: &'a str,
+    elapsed: u64,
+    moves: &'a str,
+    undone: usize,
+}
+impl Fields<'_> {
+    fn session(&self) -> Option<Session> {
+        let mut givens = Board::new(self.givens?)?;
+        let variant = self
+            .variant
+            .split_whitespace()
+            .map(Variant::from_name)
+            .try_fold(Variant::CLASSIC, |v, rule| Some(v | rule?))?;
+        givens.set_variant(variant);
+        let mut session = Session::new(givens)?;
+
+        let entries = self.entries?.chars().map(|c| match c {
+            '.' => Some(None),
+            '1'..='9' => Some(Some(c as u8 - b'0')),
+            _ => None,
+        });
+        let entries = entries.collect::<Option<Vec<_>>>()?;
+        let marks = self
+            .marks?
+            .split_whitespace()
+            .map(|group| match group {
+                "-" => Some(Candidates::from_bits(0)),
+                _ if group.chars().all(|c| ('1'..='9').contains(&c)) => {
+                    Some(Candidates::from_bits(
+                        group
+                            .chars()
+                            .fold(0, |bits, c| bits | Candidates::from(c).bits()),
+                    ))
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if entries.len() != 81 || marks.len() != 81 {
+            return None;
+        }
+
+        for token in self.moves.split_whitespace() {
+            let cell = token.get(..4)?.parse::<Cell>().ok()?;
+            let action = match token.get(4..)?.as_bytes() {
+                b"x" => Action::Clear,
+                &[b'=', n @ b'1'..=b'9'] => Action::Enter(n - b'0'),
+                &[b'm', n @ b'1'..=b'9'] => Action::Mark(n - b'0'),
+                _ => return None,
+            };
+            if !session.play(cell, action) {
+                return None;
+            }
+        }
+        for _ in 0..self.undone {
+            session.undo()?;
+        }
+        if session.entries[..] != entries[..] || session.marks[..] != marks[..] {
+            return None;
+        }
+        session.elapsed = Duration::from_secs(self.elapsed);
+        Some(session)
+    }
+}
+
+// solved cells of the board, '.' for others
+fn givens(b: &Board) -> String {
+    Format::Line.write(&[*b]).trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ripple::Ripple;
+    use crate::testing::HARD;
+
+    #[test]
+    fn round_trip() {
+        let mut s = Session::new(Board::new(HARD).unwrap()).unwrap();
+        let (a, b) = (Cell::new(0, 1), Cell::new(0, 2));
+        s.toggle_mark(b, 1);
+        s.toggle_mark(b, 4);
+        s.enter(a, 8);
+        s.enter(Cell::new(8, 8), 2);
+        s.clear(a);
+        s.undo();
+        s.set_elapsed(Duration::from_secs(125));
+
+        let text = s.save();
+        assert!(text.starts_with("sudoku-save 1\ngivens 8....."));
+        assert!(text.contains("\nelapsed 125\n"));
+        assert!(text.contains("\nmarks - - 14 - "));
+        assert!(text.contains("\nmoves r1c3m1 r1c3m4 r1c2=8 r9c9=2 r1c2x\nundone 1\n"));
+        let loaded = Session::load(&text).unwrap();
+        assert_eq!(s, loaded);
+        assert_eq!(Some(8), loaded.entry(a));
+        assert_eq!(1, loaded.undone().len());
+
+        // moves not leading to the numbers
+        let broken = text.replace("r9c9=2", "r9c9=3");
+        assert_eq!(None, Session::load(&broken));
+        // corrupt moves, e.g. a multi-byte char where the number should be
+        for token in ["r9c9é", "r9c9=0", "r9c9m", "r9c9=22", "r9é=2"].iter() {
+            let corrupt = text.replace("r9c9=2", token);
+            assert_eq!(None, Session::load(&corrupt));
+        }
+        assert_eq!(
+            None,
+            Session::load(&text.replace("sudoku-save 1", "sudoku-save 2"))
+        );
+        assert_eq!(None, Session::load(""));
+    }
+
+    #[test]
+    fn variant() {
+        // an answer of the rules with r5c5 blank
+        let mut empty = Board::new(&".".repeat(81)).unwrap();
+        empty.set_variant(Variant::ANTI_KNIGHT | Variant::DISJOINT);
+        let mut r = Ripple::from_board(empty);
+        assert!(r.do_solve());
+        let (mut givens, c) = (*r.board(), Cell::new(4, 4));
+        let n = givens.cell(c).lucky();
+        givens.set_cell(c, Candidates::new());
+
+        let mut s = Session::new(givens).unwrap();
+        s.enter(c, n as u8 - b'0');
+        let text = s.save();
+        assert!(text.contains("\nvariant anti-knight disjoint\n"));
+        assert_eq!(s, Session::load(&text).unwrap());
+        assert_eq!(None, Session::load(&text.replace("disjoint", "x")));
+        // a number no move has entered
+        let moves = format!("moves r5c5={}", n);
+        assert_eq!(None, Session::load(&text.replace(&moves, "moves")));
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt;
+
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::Cell;
+use crate::ripple::Ripple;
+
+/// Layout of a gattai(overlapping multi-grid) puzzle
+///
+/// Each 9*9 grid is placed at an offset of the composite board, offsets are
+/// multiples of 3 so the shared parts are whole blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    offsets: Vec<(usize, usize)>,
+    rows: usize,
+    cols: usize,
+}
+impl Layout {
+    pub fn new(offsets: &[(usize, usize)]) -> Option<Self> {
+        if offsets.is_empty() || offsets.iter().any(|&(r, c)| r % 3 != 0 || c % 3 != 0) {
+            return None;
+        }
+        Some(Self {
+            offsets: offsets.to_vec(),
+            rows: offsets.iter().map(|&(r, _)| r + 9).max().unwrap(),
+            cols: offsets.iter().map(|&(_, c)| c + 9).max().unwrap(),
+        })
+    }
+    /// five grids, the center one shares a corner block with each of the others
+    pub fn samurai() -> Self {
+        Self::new(&[(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)]).unwrap()
+    }
+    /// two grids sharing a corner block
+    pub fn twodoku() -> Self {
+        Self::new(&[(0, 0), (6, 6)]).unwrap()
+    }
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+    pub fn offsets(&self) -> &[(usize, usize)] {
+        &self.offsets
+    }
+    /// grids covering the cell of the composite board, with the position in each grid
+    pub fn grids_of(&self, row: usize, col: usize) -> Vec<(usize, Cell)> {
+        self.offsets
+            .iter()
+            .enumerate()
+            .filter(|(_, &(r, c))| (r..r + 9).contains(&row) && (c..c + 9).contains(&col))
+            .map(|(k, &(r, c))| (k, Cell::new(row - r, col - c)))
+            .collect()
+    }
+}
+
+/// A gattai puzzle, e.g. samurai
+///
+/// Text format: cells of the composite board in reading order, only '1-9',
+/// '0' and '.' are valid, others are ignored, e.g. a twodoku:
+/// ```text
+/// 8........
+/// ..36.....
+/// .7..9.2..
+/// .5...7...
+/// ....457..
+/// ...1...3.
+/// ..1....68......
+/// ..85...1.......
+/// .9....4........
+///       .........
+///       .........
+///       .........
+///       .........
+///       .........
+///       .........
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gattai {
+    layout: Layout,
+    grids: Vec<Board>,
+}
+impl Gattai {
+    pub fn new(layout: Layout, s: &str) -> Option<Self> {
+        let mut grids = vec![Board::default(); layout.offsets.len()];
+        let mut cells = s
+            .chars()
+            .filter(|&c| c.is_ascii_digit() || c == '.')
+            .map(Candidates::from);
+        for row in 0..layout.rows {
+            for col in 0..layout.cols {
+                let covers = layout.grids_of(row, col);
+                if covers.is_empty() {
+                    continue;
+                }
+                let cs = cells.next()?;
+                for (k, c) in covers {
+                    grids[k].set_cell(c, cs);
+                }
+            }
+        }
+        if cells.next().is_some() {
+            return None;
+        }
+        Some(Self { layout, grids })
+    }
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+    pub fn grid(&self, k: usize) -> &Board {
+        &self.grids[k]
+    }
+    /// cell of the composite board, `None` if it's not in any grid
+    pub fn cell(&self, row: usize, col: usize) -> Option<Candidates> {
+        let &(k, c) = self.layout.grids_of(row, col).first()?;
+        Some(self.grids[k].cell(c))
+    }
+
+    pub fn solve(&mut self) -> bool {
+        // shared cells of the composite board
+        let shared = (0..self.layout.rows)
+            .flat_map(|row| (0..self.layout.cols).map(move |col| (row, col)))
+            .map(|(row, col)| self.layout.grids_of(row, col))
+            .filter(|covers| covers.len() > 1)
+            .collect::<Vec<_>>();
+
+        let mut grids = self.grids.clone();
+        if !Self::propagate(&mut grids, &shared) || !Self::backtrack(&mut grids, &shared) {
+            return false;
+        }
+        self.grids = grids;
+        true
+    }
+    // ripple in each grid and sync shared cells, until nothing changes
+    fn propagate(grids: &mut [Board], shared: &[Vec<(usize, Cell)>]) -> bool {
+        loop {
+            for g in grids.iter_mut() {
+                if !Ripple::ripple_all(g) {
+                    return false;
+                }
+            }
+
+            let mut changed = false;
+            for covers in shared {
+                let bits = covers
+                    .iter()
+                    .fold(0x1FF, |bits, &(k, c)| bits & grids[k].cell(c).bits());
+                let cs = Candidates::from_bits(bits);
+                if !cs.is_valid() {
+                    return false;
+                }
+                for &(k, c) in covers {
+                    if grids[k].cell(c) != cs {
+                        grids[k].set_cell(c, cs);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return true;
+            }
+        }
+    }
+    fn backtrack(grids: &mut Vec<Board>, shared: &[Vec<(usize, Cell)>]) -> bool {
+        // the unsolved cell with least candidates of all grids
+        let mut least: Option<(usize, Cell)> = None;
+        let mut min_len = 10;
+        for (k, g) in grids.iter().enumerate() {
+            for c in Cell::all() {
+                let len = g.cell(c).len();
+                if len > 1 && len < min_len {
+                    least = Some((k, c));
+                    min_len = len;
+                }
+            }
+        }
+        let (k, c) = match least {
+            Some(cell) => cell,
+            None => return true,
+        };
+
+        let g0 = grids.clone();
+        for cs in g0[k].cell(c).iter() {
+            grids[k].set_cell(c, cs);
+            if Self::propagate(grids, shared) && Self::backtrack(grids, shared) {
+                return true;
+            }
+            grids.clone_from(&g0);
+        }
+        false
+    }
+}
+
+impl fmt::Display for Gattai {
+    /// display the composite board, solved cells as numbers, e.g. a twodoku:
+    /// +-------+-------+-------+
+    /// | 8 . . | . . . | . . . |
+    /// ...
+    /// +-------+-------+-------+-------+-------+
+    /// | . . 1 | . . . | . 6 8 | . . . | . . . |
+    /// ...
+    /// +-------+-------+-------+-------+-------+
+    ///                 | . . . | . . . | . . . |
+    /// ...
+    ///                 +-------+-------+-------+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (rows, cols) = (self.layout.rows / 3 * 4 + 1, self.layout.cols / 3 * 8 + 1);
+        let mut canvas = vec![vec![' '; cols]; rows];
+        for bi in 0..self.layout.rows / 3 {
+            for bj in 0..self.layout.cols / 3 {
+                if self.layout.grids_of(bi * 3, bj * 3).is_empty() {
+                    continue;
+                }
+                // block bounds
+                let (top, left) = (bi * 4, bj * 8);
+                for k in 0..=8 {
+                    canvas[top][left + k] = '-';
+                    canvas[top + 4][left + k] = '-';
+                }
+                for k in 0..=4 {
+                    canvas[top + k][left] = '|';
+                    canvas[top + k][left + 8] = '|';
+                }
+                for &(r, c) in [(0, 0), (0, 8), (4, 0), (4, 8)].iter() {
+                    canvas[top + r][left + c] = '+';
+                }
+                // cells
+                for i in 0..3 {
+                    for j in 0..3 {
+                        let cs = self.cell(bi * 3 + i, bj * 3 + j).unwrap();
+                        canvas[top + 1 + i][left + 2 + j * 2] =
+                            if cs.len() == 1 { cs.lucky() } else { '.' };
+                    }
+                }
+            }
+        }
+
+        for line in canvas {
+            let line = line.into_iter().collect::<String>();
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_valid(g: &Board) -> bool {
+        Cell::all()
+            .all(|c| g.cell(c).len() == 1 && g.peers_of(c).iter().all(|&p| g.cell(p) != g.cell(c)))
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(None, Layout::new(&[]));
+        assert_eq!(None, Layout::new(&[(0, 0), (4, 4)]));
+
+        let l = Layout::samurai();
+        assert_eq!((21, 21), (l.rows(), l.cols()));
+        assert_eq!(
+            vec![(0, Cell::new(6, 6)), (2, Cell::new(0, 0))],
+            l.grids_of(6, 6)
+        );
+        assert_eq!(vec![(2, Cell::new(4, 4))], l.grids_of(10, 10));
+        assert_eq!(Vec::<(usize, Cell)>::new(), l.grids_of(0, 10));
+    }
+
+    #[test]
+    fn twodoku() {
+        let s = "
+		8........
+		..36.....
+		.7..9.2..
+		.5...7...
+		....457..
+		...1...3.
+		..1....68......
+		..85...1.......
+		.9....4........
+		      .........
+		      .........
+		      .........
+		      .........
+		      .........
+		      .........
+		";
+        let mut g = Gattai::new(Layout::twodoku(), s).unwrap();
+        assert_eq!(None, Gattai::new(Layout::twodoku(), &s[..s.len() - 20]));
+        assert!(g.solve());
+        assert!(is_valid(g.grid(0)));
+        assert!(is_valid(g.grid(1)));
+        assert_eq!(
+            g.grid(0).cell(Cell::new(8, 8)),
+            g.grid(1).cell(Cell::new(2, 2))
+        );
+        assert_eq!('8', g.cell(0, 0).unwrap().lucky());
+
+        // '6' of the shared block conflicts with the second grid
+        let s = s.replacen("      .........", "      .6.......", 1);
+        let mut g = Gattai::new(Layout::twodoku(), &s).unwrap();
+        assert!(!g.solve());
+    }
+
+    #[test]
+    fn samurai() {
+        let mut g = Gattai::new(Layout::samurai(), &".".repeat(369)).unwrap();
+        assert!(g.solve());
+        for k in 0..5 {
+            assert!(is_valid(g.grid(k)));
+        }
+
+        // keep a few of the answer, solve again
+        let ans = g.to_string();
+        let mut n = 0;
+        let puzzle = ans
+            .chars()
+            .map(|c| match c {
+                '1'..='9' => {
+                    n += 1;
+                    if n % 3 == 0 {
+                        c
+                    } else {
+                        '.'
+                    }
+                }
+                c => c,
+            })
+            .collect::<String>();
+        let mut g = Gattai::new(Layout::samurai(), &puzzle).unwrap();
+        assert!(g.solve());
+        for k in 0..5 {
+            assert!(is_valid(g.grid(k)));
+        }
+        let solved = g.to_string();
+        assert_eq!(ans.len(), solved.len());
+        for (p, c) in puzzle.chars().zip(solved.chars()) {
+            if p != '.' {
+                assert_eq!(p, c);
+            }
+        }
+    }
+}
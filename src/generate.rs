@@ -0,0 +1,137 @@
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::Cell;
+use crate::hint::{self, Difficulty};
+use crate::ripple::Ripple;
+
+/// Generator of classic puzzles with a unique answer, the same seed gives the
+/// same puzzles
+#[derive(Clone, Debug)]
+pub struct Generator {
+    state: u64,
+}
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        // splitmix64 spreads nearby seeds, xorshift is stuck at 0
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Self {
+            state: if z == 0 { 0x9E37_79B9_7F4A_7C15 } else { z },
+        }
+    }
+
+    /// a random solved grid, as 81 numbers
+    pub fn grid(&mut self) -> String {
+        let mut grid = [0; 81];
+        self.fill(&mut grid, &mut [[0; 9]; 3], 0);
+        grid.iter().map(|&n| (b'0' + n) as char).collect()
+    }
+    /// a random puzzle with a unique answer, givens of a grid are removed in
+    /// random order while the answer stays unique
+    pub fn puzzle(&mut self) -> Board {
+        let mut puzzle = Board::new(&self.grid()).unwrap();
+        let mut order = (0..81).collect::<Vec<usize>>();
+        self.shuffle(&mut order);
+        for k in order {
+            let c = Cell::from_index(k);
+            let given = puzzle.cell(c);
+            puzzle.set_cell(c, Candidates::new());
+            if Ripple::from_board(puzzle).count(2) != 1 {
+                puzzle.set_cell(c, given);
+            }
+        }
+        puzzle
+    }
+    /// a puzzle rated as `difficulty`, `None` if none is found in `tries`
+    pub fn puzzle_of(&mut self, difficulty: Difficulty, tries: usize) -> Option<Board> {
+        (0..tries)
+            .map(|_| self.puzzle())
+            .find(|b| hint::rate(b).difficulty == difficulty)
+    }
+
+    // fill cells from the `k`th in reading order by backtracking, numbers are
+    // tried in random order, `used` are numbers of rows, cols and blocks as bits
+    fn fill(&mut self, grid: &mut [u8; 81], used: &mut [[u16; 9]; 3], k: usize) -> bool {
+        if k == 81 {
+            return true;
+        }
+        let c = Cell::from_index(k);
+        let units = [c.row(), c.col(), c.block()];
+        let mut digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        self.shuffle(&mut digits);
+        for &n in digits.iter() {
+            let bit = 1 << n;
+            if used.iter().zip(units.iter()).any(|(u, &i)| u[i] & bit != 0) {
+                continue;
+            }
+            for (u, &i) in used.iter_mut().zip(units.iter()) {
+                u[i] |= bit;
+            }
+            grid[k] = n;
+            if self.fill(grid, used, k + 1) {
+                return true;
+            }
+            for (u, &i) in used.iter_mut().zip(units.iter()) {
+                u[i] &= !bit;
+            }
+        }
+        false
+    }
+    // Fisher-Yates
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for k in (1..items.len()).rev() {
+            items.swap(k, self.below(k + 1));
+        }
+    }
+    // a random number in 0..n
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+    // xorshift64*
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canon::canonicalize;
+    use crate::formats::Format;
+    use std::collections::HashSet;
+
+    #[test]
+    fn grid() {
+        let mut gen = Generator::new(7);
+        let g = gen.grid();
+        assert_eq!(Some(g.clone()), Ripple::solve(&g));
+        assert_ne!(g, gen.grid());
+        assert_eq!(g, Generator::new(7).grid());
+
+        // not all of the same grid shuffled
+        let canon = |g: &str| Format::Line.write(&[canonicalize(&Board::new(g).unwrap()).unwrap()]);
+        let grids = (0..8).map(|seed| canon(&Generator::new(seed).grid()));
+        assert_eq!(8, grids.collect::<HashSet<_>>().len());
+        // the seed xorshift was stuck at before
+        let mut gen = Generator::new(0x9E37_79B9_7F4A_7C15);
+        assert_ne!(gen.grid(), gen.grid());
+    }
+
+    #[test]
+    fn puzzle() {
+        let mut gen = Generator::new(42);
+        let b = gen.puzzle();
+        assert_eq!(1, Ripple::from_board(b).count(2));
+        let givens = b.iter().flatten().filter(|cs| cs.len() == 1).count();
+        assert!(givens < 40);
+        assert_eq!(b, Generator::new(42).puzzle());
+
+        let b = gen.puzzle_of(Difficulty::Easy, 20).unwrap();
+        assert_eq!(Difficulty::Easy, hint::rate(&b).difficulty);
+    }
+}
@@ -0,0 +1,441 @@
+use std::fmt;
+
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::{Cell, Unit};
+use crate::render::Options;
+
+/// Techniques of a logical step, from the easiest one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    /// the only candidate left in a cell
+    NakedSingle,
+    /// the only place for a number in a unit
+    HiddenSingle,
+    /// a number of a box is in one row or column only, or the other way round
+    LockedCandidates,
+    /// two cells of a unit with the same two candidates
+    NakedPair,
+}
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::NakedSingle => "naked single",
+            Self::HiddenSingle => "hidden single",
+            Self::LockedCandidates => "locked candidates",
+            Self::NakedPair => "naked pair",
+        })
+    }
+}
+
+/// A logical step, either placing a number or removing candidates
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    pub technique: Technique,
+    /// the unit the step is about, if any
+    pub unit: Option<Unit>,
+    /// cells the step is based on
+    pub cells: Vec<Cell>,
+    /// numbers of `cells` the step is based on
+    pub numbers: Vec<u8>,
+    /// number placed in a cell
+    pub place: Option<(Cell, u8)>,
+    /// candidates removed, as (cell, number)
+    pub eliminations: Vec<(Cell, u8)>,
+}
+impl Hint {
+    /// place the number or remove the candidates
+    pub fn apply(&self, b: &mut Board) {
+        if let Some((c, n)) = self.place {
+            b.set_cell(c, bit(n));
+        }
+        for &(c, n) in self.eliminations.iter() {
+            b.substract(c, bit(n));
+        }
+    }
+    /// highlights of the step for the renderers
+    pub fn options(&self) -> Options {
+        Options {
+            pencil_marks: true,
+            cells: self.cells.clone(),
+            candidates: self
+                .cells
+                .iter()
+                .flat_map(|&c| self.numbers.iter().map(move |&n| (c, n)))
+                .collect(),
+            eliminated: self.eliminations.clone(),
+        }
+    }
+}
+impl fmt::Display for Hint {
+    /// e.g. "hidden single: r5c3 is 7, the only place for 7 in box 4"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.technique)?;
+        let numbers = join(self.numbers.iter());
+        match (self.technique, self.place, self.unit) {
+            (Technique::NakedSingle, Some((c, n)), _) => {
+                write!(f, "{} is {}, the only candidate left", c, n)
+            }
+            (Technique::HiddenSingle, Some((c, n)), Some(unit)) => {
+                write!(f, "{} is {}, the only place for {} in {}", c, n, n, unit)
+            }
+            (Technique::LockedCandidates, _, Some(unit)) => write!(
+                f,
+                "{} of {} is in {} only, remove it from {}",
+                numbers,
+                unit,
+                join(self.cells.iter()),
+                join(self.eliminations.iter().map(|(c, _)| c))
+            ),
+            _ => write!(
+                f,
+                "{} hold {} in {}, remove them from others",
+                join(self.cells.iter()),
+                numbers,
+                self.unit.map(|u| u.to_string()).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// the easiest logical step of the board, `None` if there is none or the
+/// board has a conflict.
+/// Candidates of unsolved cells are taken without numbers of their solved
+/// peers, as pencil marks kept up to date.
+pub fn next(b: &Board) -> Option<Hint> {
+    let g = pencil_marks(b)?;
+    naked_single(b, &g)
+        .or_else(|| hidden_single(&g))
+        .or_else(|| locked_candidates(&g))
+        .or_else(|| naked_pair(&g))
+}
+
+/// Difficulty of a puzzle, by the hardest technique it needs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    /// singles only
+    Easy,
+    /// locked candidates
+    Medium,
+    /// naked pairs
+    Hard,
+    /// guesses are needed
+    Expert,
+}
+impl Difficulty {
+    pub const NAMES: [(&'static str, Self); 4] = [
+        ("easy", Self::Easy),
+        ("medium", Self::Medium),
+        ("hard", Self::Hard),
+        ("expert", Self::Expert),
+    ];
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, d)| d)
+    }
+}
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let &(name, _) = Self::NAMES.iter().find(|(_, d)| d == self).unwrap();
+        f.write_str(name)
+    }
+}
+impl From<Technique> for Difficulty {
+    fn from(t: Technique) -> Self {
+        match t {
+            Technique::NakedSingle | Technique::HiddenSingle => Self::Easy,
+            Technique::LockedCandidates => Self::Medium,
+            Technique::NakedPair => Self::Hard,
+        }
+    }
+}
+
+/// Rating of a puzzle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rating {
+    pub difficulty: Difficulty,
+    /// logical steps taken, until solved or stuck
+    pub steps: usize,
+}
+
+/// rate the puzzle by solving it with logical steps only, it's expected to
+/// have a unique answer
+pub fn rate(b: &Board) -> Rating {
+    let mut g = *b;
+    let mut rating = Rating {
+        difficulty: Difficulty::Easy,
+        steps: 0,
+    };
+    while !g.iter().flatten().all(|cs| cs.len() == 1) {
+        match next(&g) {
+            Some(hint) => {
+                rating.difficulty = rating.difficulty.max(hint.technique.into());
+                rating.steps += 1;
+                hint.apply(&mut g);
+            }
+            None => {
+                rating.difficulty = Difficulty::Expert;
+                break;
+            }
+        }
+    }
+    rating
+}
+
+// candidates without numbers of solved peers, `None` if any cell is empty or
+// solved the same as a peer
+fn pencil_marks(b: &Board) -> Option<Board> {
+    let mut g = *b;
+    for c in Cell::all() {
+        let mut cs = b.cell(c);
+        if cs.len() == 1 {
+            if b.peers_of(c).iter().any(|&p| b.cell(p) == cs) {
+                return None;
+            }
+            continue;
+        }
+        for &p in b.peers_of(c) {
+            if b.cell(p).len() == 1 {
+                cs.substract(&b.cell(p));
+            }
+        }
+        if cs.is_empty() {
+            return None;
+        }
+        g.set_cell(c, cs);
+    }
+    Some(g)
+}
+
+fn naked_single(b: &Board, g: &Board) -> Option<Hint> {
+    let c = Cell::all().find(|&c| b.cell(c).len() > 1 && g.cell(c).len() == 1)?;
+    let n = number(g.cell(c));
+    Some(Hint {
+        technique: Technique::NakedSingle,
+        unit: None,
+        cells: vec![c],
+        numbers: vec![n],
+        place: Some((c, n)),
+        eliminations: Vec::new(),
+    })
+}
+fn hidden_single(g: &Board) -> Option<Hint> {
+    for unit in units() {
+        for n in 1..=9 {
+            let cells = with(g, unit, n);
+            if let [c] = cells[..] {
+                if g.cell(c).len() > 1 {
+                    return Some(Hint {
+                        technique: Technique::HiddenSingle,
+                        unit: Some(unit),
+                        cells: vec![c],
+                        numbers: vec![n],
+                        place: Some((c, n)),
+                        eliminations: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+fn locked_candidates(g: &Board) -> Option<Hint> {
+    for unit in units() {
+        for n in 1..=9 {
+            let cells = with(g, unit, n);
+            if cells.len() < 2 || cells.iter().any(|&c| g.cell(c).len() == 1) {
+                continue;
+            }
+            // the other unit all cells are in
+            let other = match unit {
+                Unit::Box(_) if cells.iter().all(|c| c.row() == cells[0].row()) => {
+                    Unit::Row(cells[0].row())
+                }
+                Unit::Box(_) if cells.iter().all(|c| c.col() == cells[0].col()) => {
+                    Unit::Col(cells[0].col())
+                }
+                Unit::Row(_) | Unit::Col(_)
+                    if cells.iter().all(|c| c.block() == cells[0].block()) =>
+                {
+                    Unit::Box(cells[0].block())
+                }
+                _ => continue,
+            };
+            let eliminations = with(g, other, n)
+                .into_iter()
+                .filter(|c| !cells.contains(c))
+                .map(|c| (c, n))
+                .collect::<Vec<_>>();
+            if !eliminations.is_empty() {
+                return Some(Hint {
+                    technique: Technique::LockedCandidates,
+                    unit: Some(unit),
+                    cells,
+                    numbers: vec![n],
+                    place: None,
+                    eliminations,
+                });
+            }
+        }
+    }
+    None
+}
+fn naked_pair(g: &Board) -> Option<Hint> {
+    for unit in units() {
+        let pairs = unit
+            .cells()
+            .filter(|&c| g.cell(c).len() == 2)
+            .collect::<Vec<_>>();
+        for (k, &a) in pairs.iter().enumerate() {
+            for &b in pairs[k + 1..].iter() {
+                let cs = g.cell(a);
+                if g.cell(b) != cs {
+                    continue;
+                }
+                let eliminations = unit
+                    .cells()
+                    .filter(|&c| c != a && c != b && g.cell(c).len() > 1)
+                    .flat_map(|c| cs.iter().map(move |n| (c, n)))
+                    .filter(|&(c, n)| g.cell(c).bits() & n.bits() != 0)
+                    .map(|(c, n)| (c, number(n)))
+                    .collect::<Vec<_>>();
+                if !eliminations.is_empty() {
+                    return Some(Hint {
+                        technique: Technique::NakedPair,
+                        unit: Some(unit),
+                        cells: vec![a, b],
+                        numbers: cs.iter().map(number).collect(),
+                        place: None,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+// boxes first, then rows and cols
+fn units() -> impl Iterator<Item = Unit> {
+    (0..9)
+        .map(Unit::Box)
+        .chain((0..9).map(Unit::Row))
+        .chain((0..9).map(Unit::Col))
+}
+// cells of the unit with `n` as a candidate
+fn with(g: &Board, unit: Unit, n: u8) -> Vec<Cell> {
+    unit.cells()
+        .filter(|&c| g.cell(c).bits() & (1 << (n - 1)) != 0)
+        .collect()
+}
+// the number as a candidate
+pub(crate) fn bit(n: u8) -> Candidates {
+    Candidates::from_bits(1 << (n - 1))
+}
+// number of a solved cell
+pub(crate) fn number(cs: Candidates) -> u8 {
+    cs.bits().trailing_zeros() as u8 + 1
+}
+fn join<T: fmt::Display>(items: impl Iterator<Item = T>) -> String {
+    items.map(|x| x.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{ANSWER, HARD};
+
+    #[test]
+    fn singles() {
+        // only r1c1 is unsolved
+        let b = Board::new(&format!(".{}", &ANSWER[1..])).unwrap();
+        let hint = next(&b).unwrap();
+        assert_eq!(Technique::NakedSingle, hint.technique);
+        assert_eq!(Some((Cell::new(0, 0), 8)), hint.place);
+        assert_eq!(
+            "naked single: r1c1 is 8, the only candidate left",
+            hint.to_string()
+        );
+
+        // 8 of box 1 can only be at r1c1, r2c3 and r3c2 are solved and 8s
+        // of rows 2-3 and cols 2-3 take the rest
+        let mut b = Board::new(&".".repeat(81)).unwrap();
+        for &(c, n) in [
+            ("r2c3", '3'),
+            ("r3c2", '7'),
+            ("r2c5", '8'),
+            ("r3c8", '8'),
+            ("r5c2", '8'),
+            ("r7c3", '8'),
+        ]
+        .iter()
+        {
+            b.set_cell(c.parse().unwrap(), Candidates::from(n));
+        }
+        let hint = next(&b).unwrap();
+        assert_eq!(Technique::HiddenSingle, hint.technique);
+        assert_eq!(Some((Cell::new(0, 0), 8)), hint.place);
+        assert_eq!(
+            "hidden single: r1c1 is 8, the only place for 8 in box 1",
+            hint.to_string()
+        );
+        let mut g = b;
+        hint.apply(&mut g);
+        assert_eq!('8', g.lucky(Cell::new(0, 0)));
+    }
+
+    #[test]
+    fn locked_and_pair() {
+        let mut b = Board::new(&".".repeat(81)).unwrap();
+        // 1 of box 1 is in row 1 only
+        for &c in [Cell::new(1, 0), Cell::new(1, 1), Cell::new(1, 2)].iter() {
+            b.set_cell(c, Candidates::from_bits(0b11111110));
+        }
+        for &c in [Cell::new(2, 0), Cell::new(2, 1), Cell::new(2, 2)].iter() {
+            b.set_cell(c, Candidates::from_bits(0b11111110));
+        }
+        let hint = locked_candidates(&b).unwrap();
+        assert_eq!(Some(Unit::Box(0)), hint.unit);
+        assert_eq!(3, hint.cells.len());
+        assert_eq!(6, hint.eliminations.len());
+        assert!(hint.eliminations.contains(&(Cell::new(0, 8), 1)));
+        assert_eq!(
+            "locked candidates: 1 of box 1 is in r1c1, r1c2, r1c3 only, \
+             remove it from r1c4, r1c5, r1c6, r1c7, r1c8, r1c9",
+            hint.to_string()
+        );
+        let opts = hint.options();
+        assert_eq!(3, opts.candidates.len());
+        assert_eq!(6, opts.eliminated.len());
+
+        let mut b = Board::new(&".".repeat(81)).unwrap();
+        b.set_cell(Cell::new(4, 0), Candidates::from_bits(0b101));
+        b.set_cell(Cell::new(4, 8), Candidates::from_bits(0b101));
+        let hint = naked_pair(&b).unwrap();
+        assert_eq!(Some(Unit::Row(4)), hint.unit);
+        assert_eq!(vec![1, 3], hint.numbers);
+        assert_eq!(14, hint.eliminations.len());
+        assert!(hint
+            .to_string()
+            .starts_with("naked pair: r5c1, r5c9 hold 1, 3 in row 5"));
+    }
+
+    #[test]
+    fn rating() {
+        assert_eq!(None, Difficulty::from_name("impossible"));
+        assert_eq!(Some(Difficulty::Hard), Difficulty::from_name("hard"));
+        assert_eq!("medium", Difficulty::Medium.to_string());
+
+        let easy =
+            "..28.691.8.1......3....1.256.9.1...4...659...1...2.7.943.1....2......1.7.179.45..";
+        let r = rate(&Board::new(easy).unwrap());
+        assert_eq!(Difficulty::Easy, r.difficulty);
+        assert_eq!(easy.matches('.').count(), r.steps);
+        assert_eq!(
+            Difficulty::Expert,
+            rate(&Board::new(HARD).unwrap()).difficulty
+        );
+    }
+}
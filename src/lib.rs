@@ -1,3 +1,19 @@
 pub mod board;
 pub mod candidates;
+pub mod canon;
+pub mod cell;
+pub mod constraint;
+pub mod formats;
+pub mod game;
+pub mod gattai;
+pub mod generate;
+pub mod hint;
+pub mod packed;
+pub mod pairwise;
+pub mod render;
 pub mod ripple;
+pub mod share;
+pub mod variant;
+
+#[cfg(test)]
+mod testing;
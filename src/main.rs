@@ -1,32 +1,925 @@
-use clap::{App, AppSettings, Arg};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant};
 
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+#[cfg(feature = "play")]
+mod play;
+#[cfg(feature = "serve")]
+mod serve;
+#[cfg(all(test, any(feature = "play", feature = "serve")))]
+#[path = "testing.rs"]
+mod testing;
+
+use sudoku::board::Board;
+use sudoku::canon::canonicalize;
+use sudoku::formats::{self, Format};
+#[cfg(feature = "play")]
+use sudoku::game::Session;
+use sudoku::generate::Generator;
+use sudoku::hint::{self, Difficulty};
+use sudoku::render::terminal;
 use sudoku::ripple::*;
+use sudoku::variant::Variant;
+
+/// exit codes, 1 is taken by clap for usage errors
+const INVALID: i32 = 2;
+const UNSOLVABLE: i32 = 3;
+const MULTIPLE: i32 = 4;
+const NOT_FOUND: i32 = 5;
+
+/// Formats of results, `Text` for humans and the others for machines
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Text,
+    /// an object for a puzzle, an array of them in batch mode
+    Json,
+    /// an object per line
+    Jsonl,
+    /// a header and a row per puzzle
+    Csv,
+}
+impl Output {
+    const NAMES: [(&'static str, Self); 4] = [
+        ("text", Self::Text),
+        ("json", Self::Json),
+        ("jsonl", Self::Jsonl),
+        ("csv", Self::Csv),
+    ];
+    fn from_args(sub: &ArgMatches) -> Self {
+        sub.value_of("FORMAT")
+            .and_then(|name| Self::NAMES.iter().find(|(n, _)| *n == name))
+            .map_or(Self::Text, |&(_, output)| output)
+    }
+}
+
+/// Result of a puzzle in machine formats
+struct Record {
+    /// the puzzle as a line, or the line read in batch mode if it's invalid
+    input: String,
+    /// "solved", "multiple", "unsolvable" or "invalid"
+    status: &'static str,
+    solution: Option<String>,
+    /// number of answers, 2 for 2 or more
+    solutions: usize,
+    stats: Stats,
+    time: Duration,
+}
+impl Record {
+    const CSV_HEADER: &'static str = "input,status,solution,solutions,tries,triplexes,time_us";
+
+    fn new(input: String, report: Report) -> Self {
+        Self {
+            input,
+            status: match report.solutions {
+                0 => "unsolvable",
+                1 => "solved",
+                _ => "multiple",
+            },
+            solution: report.answer.solution,
+            solutions: report.solutions,
+            stats: report.answer.stats,
+            time: report.time,
+        }
+    }
+    fn invalid(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            status: "invalid",
+            solution: None,
+            solutions: 0,
+            stats: Stats::default(),
+            time: Duration::default(),
+        }
+    }
+    // e.g. {"input":"8....","status":"solved","solution":"8127...","solutions":1,
+    // "tries":5,"triplexes":2,"time_us":870}
+    fn json(&self) -> String {
+        format!(
+			"{{\"input\":{},\"status\":\"{}\",\"solution\":{},\"solutions\":{},\"tries\":{},\"triplexes\":{},\"time_us\":{}}}",
+			json_string(&self.input),
+			self.status,
+			self.solution.as_deref().map_or("null".to_string(), json_string),
+			self.solutions,
+			self.stats.tries,
+			self.stats.triplexes,
+			self.time.as_micros()
+		)
+    }
+    fn csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            csv_field(&self.input),
+            self.status,
+            self.solution.as_deref().unwrap_or(""),
+            self.solutions,
+            self.stats.tries,
+            self.stats.triplexes,
+            self.time.as_micros()
+        )
+    }
+}
+
+/// Records of a batch written as they come, an array of them in `Json`
+struct Records<W: Write> {
+    out: W,
+    output: Output,
+    written: usize,
+}
+impl<W: Write> Records<W> {
+    /// the header of the format is written first
+    fn new(mut out: W, output: Output) -> io::Result<Self> {
+        match output {
+            Output::Json => write!(out, "[")?,
+            Output::Csv => writeln!(out, "{}", Record::CSV_HEADER)?,
+            _ => {}
+        }
+        Ok(Self {
+            out,
+            output,
+            written: 0,
+        })
+    }
+    fn write(&mut self, record: &Record) -> io::Result<()> {
+        match self.output {
+            Output::Text => writeln!(
+                self.out,
+                "{}",
+                record.solution.as_deref().unwrap_or(record.status)
+            )?,
+            Output::Json => {
+                let sep = if self.written == 0 { "\n" } else { ",\n" };
+                write!(self.out, "{}{}", sep, record.json())?
+            }
+            Output::Jsonl => writeln!(self.out, "{}", record.json())?,
+            Output::Csv => writeln!(self.out, "{}", record.csv())?,
+        }
+        self.written += 1;
+        Ok(())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+    /// the array is closed, `[]` if it's empty
+    fn finish(mut self) -> io::Result<W> {
+        if self.output == Output::Json {
+            let tail = if self.written == 0 { "]" } else { "\n]" };
+            writeln!(self.out, "{}", tail)?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
 
 pub fn main() {
-	let args = App::new("Shudu")
+    let format = Arg::with_name("FORMAT")
+		.long("format")
+		.takes_value(true)
+		.default_value("text")
+		.possible_values(&Output::NAMES.iter().map(|(name, _)| *name).collect::<Vec<_>>())
+		.help("Output format, machine formats include the input, the number of answers, stats and time");
+    let generated = [
+        Arg::with_name("SEED")
+            .long("seed")
+            .takes_value(true)
+            .help("Seed of the puzzle, random by default"),
+        Arg::with_name("DIFFICULTY")
+            .long("difficulty")
+            .takes_value(true)
+            .possible_values(
+                &Difficulty::NAMES
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>(),
+            )
+            .help("Difficulty of the puzzle, any by default"),
+    ];
+    let input = [
+        Arg::with_name("PUZZLE")
+            .help("The puzzle, read from stdin if neither it nor a file is given"),
+        Arg::with_name("FILE")
+            .short("f")
+            .long("file")
+            .takes_value(true)
+            .conflicts_with("PUZZLE")
+            .help("File of one puzzle, the format is guessed by the extension"),
+    ];
+    let args = App::new("Shudu")
 		.version("0.1.0")
 		.author("Simon Shi <simonshi@gmail.com>")
 		.about("Shudo(sudoku) solver in Rust")
 		.setting(AppSettings::ArgRequiredElseHelp)
+		.setting(AppSettings::VersionlessSubcommands)
+		.after_help(
+			"EXIT CODES:\n    0  ok\n    1  bad arguments or terminal errors\n    2  invalid input\n    3  no answer\n    \
+			 4  multiple answers\n    5  no hint or puzzle found",
+		)
 		.arg(
 			Arg::with_name("PUZZLE")
 				.short("p")
 				.long("puzzle")
 				.takes_value(true)
-				.required(true)
-				.help("The puzzle, only '1-9' and '.' are valid, others are ignored"),
+				.help("The puzzle to solve, the same as `solve <PUZZLE>`"),
+		)
+		.arg(format.clone())
+		.arg(
+			Arg::with_name("VARIANT")
+				.long("variant")
+				.takes_value(true)
+				.multiple(true)
+				.number_of_values(1)
+				.global(true)
+				.possible_values(&Variant::NAMES.iter().map(|(name, _)| *name).collect::<Vec<_>>())
+				.help("Variant rules, can be given more than once"),
+		)
+		.arg(
+			Arg::with_name("NO_COLOR")
+				.long("no-color")
+				.global(true)
+				.help("Plain output, the default if stdout is not a terminal or NO_COLOR is set"),
+		)
+		.subcommand(
+			SubCommand::with_name("solve")
+				.about("Solve the puzzle")
+				.args(&input)
+				.arg(format.clone()),
+		)
+		.subcommand(
+			SubCommand::with_name("count")
+				.about("Count answers of the puzzle")
+				.args(&input)
+				.arg(
+					Arg::with_name("LIMIT")
+						.long("limit")
+						.takes_value(true)
+						.default_value("1000")
+						.help("Stop counting at the limit"),
+				),
+		)
+		.subcommand(
+			SubCommand::with_name("generate")
+				.about("Generate a classic puzzle with a unique answer")
+				.args(&generated),
+		)
+		.subcommand(SubCommand::with_name("rate").about("Rate the difficulty of the puzzle").args(&input))
+		.subcommand(SubCommand::with_name("hint").about("Show the next logical step").args(&input))
+		.subcommand(
+			SubCommand::with_name("validate")
+				.about("Check the puzzle has a unique answer")
+				.args(&input),
+		)
+		.subcommand(
+			SubCommand::with_name("batch")
+				.about("Solve one puzzle per line, from stdin by default")
+				.after_help(
+					"In text, each line of the output is the answer, or `invalid` or `unsolvable`. Other \
+					 formats count answers up to 2 as well. Comment lines are skipped. Statistics are \
+					 printed to stderr at the end.",
+				)
+				.arg(
+					Arg::with_name("FILE")
+						.short("f")
+						.long("file")
+						.takes_value(true)
+						.help("File of puzzles, one per line"),
+				)
+				.arg(
+					Arg::with_name("JOBS")
+						.short("j")
+						.long("jobs")
+						.takes_value(true)
+						.default_value("1")
+						.help("Threads solving puzzles, 0 for all cores"),
+				)
+				.arg(format),
+		)
+		.subcommand(
+			SubCommand::with_name("play")
+				.about("Play the puzzle in the terminal, a generated one if none is given")
+				.args(&input[..1])
+				.arg(
+					Arg::with_name("SAVE")
+						.long("save")
+						.takes_value(true)
+						.help("File of the game, it's resumed if the file exists, and saved on quit"),
+				)
+				.arg(
+					Arg::with_name("FILE")
+						.short("f")
+						.long("file")
+						.takes_value(true)
+						.conflicts_with("PUZZLE")
+						.help("File of one puzzle, the format is guessed by the extension"),
+				)
+				.args(&generated),
+		)
+		.subcommand(
+			SubCommand::with_name("serve")
+				.about("Serve solve, count, hint, rate and generate as JSON-RPC")
+				.after_help(
+					"A JSON-RPC 2.0 request, or a batch of them, per line of stdin, and a response per \
+					 line of stdout. Params are named, `puzzle` is a string or a board object, with \
+					 optional `variant`, e.g.\n{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"solve\", \
+					 \"params\": {\"puzzle\": \"8..........36...\"}}",
+				)
+				.arg(
+					Arg::with_name("STDIO")
+						.long("stdio")
+						.required(true)
+						.help("Serve over stdin and stdout, the only transport for now"),
+				),
+		)
+		.subcommand(
+			SubCommand::with_name("canonicalize")
+				.about("Print the canonical form of a classic puzzle")
+				.args(&input),
 		)
 		.get_matches();
 
-	let puzzle = args.value_of("PUZZLE").unwrap();
-
-	if let Some(mut game) = Ripple::new(&puzzle) {
-		if game.do_solve() {
-			println!("answer:\n{}", game.to_string());
-		} else {
-			println!("no answer")
-		}
-	} else {
-		println!("invalid input")
-	}
+    let code = match args.subcommand() {
+        ("solve", Some(sub)) => solve(&args, sub),
+        ("count", Some(sub)) => match sub.value_of("LIMIT").unwrap().parse() {
+            Ok(limit) => with_puzzle(&args, sub, |g| {
+                let n = Ripple::from_board(g).count(limit);
+                println!("{}", n);
+                match n {
+                    0 => UNSOLVABLE,
+                    1 => 0,
+                    _ => MULTIPLE,
+                }
+            }),
+            Err(_) => invalid(),
+        },
+        ("generate", Some(sub)) => generate(sub),
+        ("rate", Some(sub)) => with_puzzle(&args, sub, rate),
+        ("hint", Some(sub)) => with_puzzle(&args, sub, show_hint),
+        ("validate", Some(sub)) => with_puzzle(&args, sub, |g| {
+            let code = unique(g);
+            if code == 0 {
+                println!("valid");
+            }
+            code
+        }),
+        ("batch", Some(sub)) => match sub.value_of("JOBS").unwrap().parse() {
+            Ok(jobs) => batch(
+                variant(&args, sub),
+                sub.value_of("FILE"),
+                jobs,
+                Output::from_args(sub),
+            ),
+            Err(_) => invalid(),
+        },
+        ("play", Some(sub)) => play(&args, sub),
+        ("serve", Some(_)) => serve(),
+        ("canonicalize", Some(sub)) => with_puzzle(&args, sub, |g| match canonicalize(&g) {
+            Some(canon) => {
+                print!("{}", Format::Line.write(&[canon]));
+                0
+            }
+            None => invalid(),
+        }),
+        _ => solve(&args, &args),
+    };
+    process::exit(code);
+}
+
+// run `f` with the puzzle of the args, of the variant rules
+fn with_puzzle(args: &ArgMatches, sub: &ArgMatches, f: impl FnOnce(Board) -> i32) -> i32 {
+    match read(sub) {
+        Some(mut g) => {
+            g.set_variant(variant(args, sub));
+            f(g)
+        }
+        None => invalid(),
+    }
+}
+fn variant(args: &ArgMatches, sub: &ArgMatches) -> Variant {
+    args.values_of("VARIANT")
+        .into_iter()
+        .chain(sub.values_of("VARIANT"))
+        .flatten()
+        .filter_map(Variant::from_name)
+        .fold(Variant::CLASSIC, |v, rule| v | rule)
+}
+// the puzzle from the arg, the file or stdin
+fn read(sub: &ArgMatches) -> Option<Board> {
+    if let Some(puzzle) = sub.value_of("PUZZLE") {
+        return parse(puzzle);
+    }
+    let mut text = String::new();
+    match sub.value_of("FILE") {
+        Some(path) => {
+            let path = Path::new(path);
+            text = std::fs::read_to_string(path).ok()?;
+            if let Ok(puzzles) = Format::from_path(path).read(&text) {
+                return if puzzles.len() == 1 {
+                    Some(puzzles[0])
+                } else {
+                    None
+                };
+            }
+        }
+        None => {
+            std::io::stdin().read_to_string(&mut text).ok()?;
+        }
+    }
+    parse(&text)
+}
+// a line, a pencil-mark grid, or 81 cells with anything between them
+fn parse(s: &str) -> Option<Board> {
+    let s = s.trim();
+    formats::parse_line(s)
+        .or_else(|| Board::from_pencil_marks(s))
+        .or_else(|| Board::new(s))
+}
+fn color(args: &ArgMatches) -> bool {
+    let no_color = args.is_present("NO_COLOR")
+        || args
+            .subcommand()
+            .1
+            .is_some_and(|sub| sub.is_present("NO_COLOR"));
+    !no_color && std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+}
+fn invalid() -> i32 {
+    println!("invalid input");
+    INVALID
+}
+
+// 0 if the puzzle has a unique answer, or the exit code
+fn unique(g: Board) -> i32 {
+    match Ripple::from_board(g).count(2) {
+        0 => {
+            println!("no answer");
+            UNSOLVABLE
+        }
+        1 => 0,
+        _ => {
+            println!("multiple answers");
+            MULTIPLE
+        }
+    }
+}
+
+fn solve(args: &ArgMatches, sub: &ArgMatches) -> i32 {
+    let output = Output::from_args(sub);
+    if output == Output::Text {
+        return with_puzzle(args, sub, |g| solve_text(g, color(args)));
+    }
+    if output == Output::Csv {
+        println!("{}", Record::CSV_HEADER);
+    }
+    let record = match read(sub) {
+        Some(mut g) => {
+            g.set_variant(variant(args, sub));
+            let input = Format::Line.write(&[g]).trim_end().to_string();
+            Record::new(input, count_batch(&[g], 2, 1).remove(0))
+        }
+        None => Record::invalid(sub.value_of("PUZZLE").unwrap_or("")),
+    };
+    if output == Output::Csv {
+        println!("{}", record.csv());
+    } else {
+        println!("{}", record.json());
+    }
+    match record.status {
+        "invalid" => INVALID,
+        "unsolvable" => UNSOLVABLE,
+        "multiple" => MULTIPLE,
+        _ => 0,
+    }
+}
+fn solve_text(givens: Board, color: bool) -> i32 {
+    let mut game = Ripple::from_board(givens);
+    match game.count(2) {
+        0 => {
+            println!("no answer");
+            UNSOLVABLE
+        }
+        n => {
+            print!(
+                "answer:\n{}",
+                terminal::render(&givens, game.board(), color)
+            );
+            if n > 1 {
+                println!("multiple answers, this is one of them");
+                MULTIPLE
+            } else {
+                0
+            }
+        }
+    }
+}
+fn rate(g: Board) -> i32 {
+    let code = unique(g);
+    if code == 0 {
+        let rating = hint::rate(&g);
+        println!("{} ({} steps)", rating.difficulty, rating.steps);
+    }
+    code
+}
+fn show_hint(g: Board) -> i32 {
+    if Ripple::from_board(g).count(1) == 0 {
+        println!("no answer");
+        return UNSOLVABLE;
+    }
+    match hint::next(&g) {
+        Some(hint) => {
+            println!("{}", hint);
+            0
+        }
+        None => {
+            println!("no hint");
+            NOT_FOUND
+        }
+    }
+}
+fn generate(sub: &ArgMatches) -> i32 {
+    match generated(sub) {
+        Ok(puzzle) => {
+            print!("{}", Format::Line.write(&[puzzle]));
+            0
+        }
+        Err(code) => code,
+    }
+}
+// a puzzle of the seed and the difficulty, or the exit code
+fn generated(sub: &ArgMatches) -> Result<Board, i32> {
+    let seed = match sub.value_of("SEED").map(str::parse) {
+        Some(Ok(seed)) => seed,
+        Some(Err(_)) => return Err(invalid()),
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64),
+    };
+    let mut generator = Generator::new(seed);
+    let puzzle = match sub.value_of("DIFFICULTY").and_then(Difficulty::from_name) {
+        Some(difficulty) => generator.puzzle_of(difficulty, 100),
+        None => Some(generator.puzzle()),
+    };
+    puzzle.ok_or_else(|| {
+        println!("no puzzle found");
+        NOT_FOUND
+    })
+}
+#[cfg(feature = "play")]
+fn play(args: &ArgMatches, sub: &ArgMatches) -> i32 {
+    let save = sub.value_of("SAVE").map(Path::new);
+    let mut session = match save.filter(|path| path.exists()) {
+        // resume the saved game
+        Some(path) => match std::fs::read_to_string(path)
+            .ok()
+            .as_deref()
+            .and_then(Session::load)
+        {
+            Some(session) => session,
+            None => return invalid(),
+        },
+        None => {
+            let givens = if sub.is_present("PUZZLE") || sub.is_present("FILE") {
+                match read(sub) {
+                    Some(mut g) => {
+                        g.set_variant(variant(args, sub));
+                        g
+                    }
+                    None => return invalid(),
+                }
+            } else {
+                match generated(sub) {
+                    Ok(g) => g,
+                    Err(code) => return code,
+                }
+            };
+            let code = unique(givens);
+            if code != 0 {
+                return code;
+            }
+            Session::new(givens).unwrap()
+        }
+    };
+    let terminal = play::run(&mut session);
+    if let Err(e) = &terminal {
+        eprintln!("terminal error: {}", e);
+    }
+    // saved even if the terminal fails, not to lose the moves
+    if let Some(path) = save {
+        if let Err(e) = std::fs::write(path, session.save()) {
+            eprintln!("can't save the game: {}", e);
+            return 1;
+        }
+    }
+    if terminal.is_err() {
+        return 1;
+    }
+    if session.is_complete() {
+        println!("solved in {}s", session.elapsed().as_secs());
+    }
+    0
+}
+#[cfg(not(feature = "play"))]
+fn play(_: &ArgMatches, _: &ArgMatches) -> i32 {
+    eprintln!("play isn't built, it needs feature `play`");
+    1
+}
+
+#[cfg(feature = "serve")]
+fn serve() -> i32 {
+    match serve::run() {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("serve error: {}", e);
+            1
+        }
+    }
+}
+#[cfg(not(feature = "serve"))]
+fn serve() -> i32 {
+    eprintln!("serve isn't built, it needs feature `serve`");
+    1
+}
+
+// lines of puzzles read and solved at a time by several threads, one thread
+// solves them one by one
+const BATCH_LINES: usize = 4096;
+
+// solve puzzles line by line, results are written in order as soon as their
+// block of lines is solved
+fn batch(variant: Variant, path: Option<&str>, jobs: usize, output: Output) -> i32 {
+    let mut input: Box<dyn BufRead> = match path {
+        Some(path) => match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(_) => return invalid(),
+        },
+        None => Box::new(std::io::stdin().lock()),
+    };
+
+    let start = Instant::now();
+    let (mut solved, mut multiple, mut unsolvable, mut invalid) = (0, 0, 0, 0);
+    let mut stats = Stats::default();
+    let mut read_error = None;
+    let mut lines = lines(&mut input, &mut read_error)
+        .filter(|line| !matches!(line, Ok(line) if formats::is_comment(line)));
+    let block_lines = if jobs == 1 { 1 } else { BATCH_LINES };
+    // e.g. the pipe is closed
+    let mut records = match Records::new(BufWriter::new(io::stdout().lock()), output) {
+        Ok(records) => records,
+        Err(_) => return 0,
+    };
+    loop {
+        let block = lines.by_ref().take(block_lines).collect::<Vec<_>>();
+        if block.is_empty() {
+            break;
+        }
+        // puzzles of the block, `None` for invalid lines
+        let boards = block
+            .iter()
+            .map(|line| {
+                line.as_deref()
+                    .ok()
+                    .and_then(formats::parse_line)
+                    .map(|mut g| {
+                        g.set_variant(variant);
+                        g
+                    })
+            })
+            .collect::<Vec<_>>();
+        let puzzles = boards.iter().flatten().copied().collect::<Vec<_>>();
+        // text doesn't show the number of answers, so it's not counted
+        let mut reports = if output == Output::Text {
+            solve_batch(&puzzles, jobs)
+                .into_iter()
+                .map(|answer| Report {
+                    solutions: answer.solution.is_some() as usize,
+                    answer,
+                    time: Duration::default(),
+                })
+                .collect::<Vec<_>>()
+        } else {
+            count_batch(&puzzles, 2, jobs)
+        }
+        .into_iter();
+        for (line, g) in block.iter().zip(boards.iter()) {
+            let (Ok(line) | Err(line)) = line;
+            let record = match g.and_then(|_| reports.next()) {
+                Some(report) => {
+                    let input = line.trim().get(..81).unwrap_or_default().to_string();
+                    Record::new(input, report)
+                }
+                None => Record::invalid(line),
+            };
+            stats += record.stats;
+            match record.status {
+                "solved" => solved += 1,
+                "multiple" => multiple += 1,
+                "unsolvable" => unsolvable += 1,
+                _ => invalid += 1,
+            }
+            if records.write(&record).is_err() {
+                return 0;
+            }
+        }
+        if records.flush().is_err() {
+            return 0;
+        }
+    }
+    if records.finish().is_err() {
+        return 0;
+    }
+    drop(lines);
+
+    let elapsed = start.elapsed().as_secs_f64();
+    let total = solved + multiple + unsolvable + invalid;
+    if output == Output::Text {
+        eprintln!(
+            "puzzles: {}, solved: {}, unsolvable: {}, invalid: {}",
+            total, solved, unsolvable, invalid
+        );
+    } else {
+        eprintln!(
+            "puzzles: {}, solved: {}, multiple: {}, unsolvable: {}, invalid: {}",
+            total, solved, multiple, unsolvable, invalid
+        );
+    }
+    eprintln!("tries: {}, triplexes: {}", stats.tries, stats.triplexes);
+    eprintln!(
+        "time: {:.3}s, {:.0} puzzles/s",
+        elapsed,
+        if elapsed > 0.0 {
+            total as f64 / elapsed
+        } else {
+            0.0
+        }
+    );
+    // the answers are cut short, it mustn't look like a whole run
+    if let Some(e) = read_error {
+        eprintln!("can't read the input: {}", e);
+        return 1;
+    }
+    0
+}
+// lines of the input, the ones not of UTF-8 are kept as `Err` of their lossy
+// text as they're invalid, and a read error ends them, kept in `error`
+fn lines<'a>(
+    input: &'a mut dyn BufRead,
+    error: &'a mut Option<io::Error>,
+) -> impl Iterator<Item = Result<String, String>> + 'a {
+    std::iter::from_fn(move || {
+        let mut buf = Vec::new();
+        match input.read_until(b'\n', &mut buf) {
+            Ok(0) => None,
+            Err(e) => {
+                *error = Some(e);
+                None
+            }
+            Ok(_) => {
+                if buf.ends_with(b"\n") {
+                    buf.pop();
+                    if buf.ends_with(b"\r") {
+                        buf.pop();
+                    }
+                }
+                Some(
+                    String::from_utf8(buf)
+                        .map_err(|e| String::from_utf8_lossy(e.as_bytes()).into_owned()),
+                )
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(input: &str) -> Record {
+        let report = Report {
+            answer: Answer {
+                solution: Some("8127".to_string()),
+                stats: Stats {
+                    tries: 5,
+                    triplexes: 2,
+                },
+            },
+            solutions: 1,
+            time: Duration::from_micros(870),
+        };
+        Record::new(input.to_string(), report)
+    }
+    fn written(output: Output, records: &[Record]) -> String {
+        let mut w = Records::new(Vec::new(), output).unwrap();
+        for r in records {
+            w.write(r).unwrap();
+        }
+        String::from_utf8(w.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(r#""8...""#, json_string("8..."));
+        assert_eq!(
+            r#""a\"b\\c\nd\u0009e\u001f""#,
+            json_string("a\"b\\c\nd\te\u{1f}")
+        );
+        assert_eq!("é", &json_string("é")[1..3]);
+        assert_eq!("8...", csv_field("8..."));
+        assert_eq!(r#""a,b""#, csv_field("a,b"));
+        assert_eq!(r#""a""b""#, csv_field("a\"b"));
+        assert_eq!("\"a\nb\"", csv_field("a\nb"));
+        assert_eq!("\"a\rb\"", csv_field("a\rb"));
+    }
+
+    #[test]
+    fn records() {
+        let r = record("8...");
+        assert_eq!(
+            r#"{"input":"8...","status":"solved","solution":"8127","solutions":1,"tries":5,"triplexes":2,"time_us":870}"#,
+            r.json()
+        );
+        assert_eq!("8...,solved,8127,1,5,2,870", r.csv());
+
+        let r = Record::invalid("x,\"y\"\t");
+        assert_eq!(
+            r#"{"input":"x,\"y\"\u0009","status":"invalid","solution":null,"solutions":0,"tries":0,"triplexes":0,"time_us":0}"#,
+            r.json()
+        );
+        assert_eq!(r#""x,""y""	",invalid,,0,0,0,0"#, r.csv());
+    }
+
+    #[test]
+    fn batch_lines() {
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken"))
+            }
+        }
+        let mut error = None;
+        let mut input = BufReader::new(io::Cursor::new(b"a\r\n\xff\n\nb".to_vec()).chain(Broken));
+        let read = lines(&mut input, &mut error).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Ok("a".to_string()),
+                Err("\u{fffd}".to_string()),
+                Ok(String::new())
+            ],
+            read
+        );
+        assert_eq!("broken", error.unwrap().to_string());
+
+        let mut error = None;
+        let mut input = io::Cursor::new(b"a\nb".to_vec());
+        assert_eq!(2, lines(&mut input, &mut error).count());
+        assert!(error.is_none());
+    }
+
+    #[test]
+    fn batch_framing() {
+        let records = [record("a"), Record::invalid("b")];
+        let (a, b) = (records[0].json(), records[1].json());
+        assert_eq!("[]\n", written(Output::Json, &[]));
+        assert_eq!(
+            format!("[\n{}\n]\n", a),
+            written(Output::Json, &records[..1])
+        );
+        assert_eq!(
+            format!("[\n{},\n{}\n]\n", a, b),
+            written(Output::Json, &records)
+        );
+        assert_eq!("", written(Output::Jsonl, &[]));
+        assert_eq!(format!("{}\n{}\n", a, b), written(Output::Jsonl, &records));
+
+        let header = Record::CSV_HEADER;
+        assert_eq!(format!("{}\n", header), written(Output::Csv, &[]));
+        let (a, b) = (records[0].csv(), records[1].csv());
+        assert_eq!(
+            format!("{}\n{}\n{}\n", header, a, b),
+            written(Output::Csv, &records)
+        );
+        assert_eq!("8127\ninvalid\n", written(Output::Text, &records));
+    }
 }
@@ -0,0 +1,209 @@
+use crate::board::Board;
+use crate::candidates::Candidates;
+
+/// Compact binary encoding of puzzles and solved grids, e.g. for a key-value store
+///
+/// Numbers are packed as a mixed-radix integer: each number is ranked among
+/// the numbers not yet used by its row, column and block, in reading order,
+/// so a number with only one choice takes no space at all.
+///
+/// - puzzle: 11 bytes as a bitmap of the givens, then the packed givens,
+///   about 21 bytes for a puzzle of 25 givens
+/// - grid: only the packed numbers, at most 13 bytes
+///
+/// Only the classic rules are used, variant and pencil marks are not kept.
+pub fn pack_puzzle(b: &Board) -> Option<Vec<u8>> {
+    let mut bytes = vec![0; 11];
+    for (k, cs) in b.iter().flatten().enumerate() {
+        if cs.is_done() {
+            bytes[k / 8] |= 1 << (k % 8);
+        }
+    }
+    bytes.extend(pack(b)?);
+    Some(bytes)
+}
+pub fn unpack_puzzle(bytes: &[u8]) -> Option<Board> {
+    if bytes.len() < 11 || bytes[10] >> 1 != 0 {
+        return None;
+    }
+    let (bitmap, packed) = bytes.split_at(11);
+    unpack(packed, |k| bitmap[k / 8] & (1 << (k % 8)) != 0)
+}
+/// the solved grid, `None` if any cell is unsolved or conflicts with its peers
+pub fn pack_grid(b: &Board) -> Option<Vec<u8>> {
+    if !b.iter().flatten().all(Candidates::is_done) {
+        return None;
+    }
+    pack(b)
+}
+pub fn unpack_grid(bytes: &[u8]) -> Option<Board> {
+    unpack(bytes, |_| true)
+}
+
+// numbers used by the houses of each row, column and block
+#[derive(Default)]
+struct Used {
+    rows: [u16; 9],
+    cols: [u16; 9],
+    blocks: [u16; 9],
+}
+impl Used {
+    fn free(&self, k: usize) -> u16 {
+        let (i, j) = (k / 9, k % 9);
+        0x1FF & !(self.rows[i] | self.cols[j] | self.blocks[i / 3 * 3 + j / 3])
+    }
+    fn set(&mut self, k: usize, bit: u16) {
+        let (i, j) = (k / 9, k % 9);
+        self.rows[i] |= bit;
+        self.cols[j] |= bit;
+        self.blocks[i / 3 * 3 + j / 3] |= bit;
+    }
+}
+// rank of `bit` among the bits of `free`
+fn rank(free: u16, bit: u16) -> u32 {
+    (free & (bit - 1)).count_ones()
+}
+
+// solved cells of the board, `None` if they conflict with each other
+fn pack(b: &Board) -> Option<Vec<u8>> {
+    let mut used = Used::default();
+    let mut digits = Vec::with_capacity(81);
+    for (k, cs) in b.iter().flatten().enumerate() {
+        if !cs.is_done() {
+            continue;
+        }
+        let (free, bit) = (used.free(k), cs.bits());
+        if free & bit == 0 {
+            return None;
+        }
+        digits.push((rank(free, bit), free.count_ones()));
+        used.set(k, bit);
+    }
+
+    // the first number is the lowest digit
+    let mut n = Vec::new();
+    for &(d, radix) in digits.iter().rev() {
+        mul_add(&mut n, radix, d);
+    }
+    Some(n)
+}
+// cells selected by `is_set` in reading order, others are blank
+fn unpack(bytes: &[u8], is_set: impl Fn(usize) -> bool) -> Option<Board> {
+    // one encoding for each board, packed numbers have no trailing zeros
+    if bytes.last() == Some(&0) {
+        return None;
+    }
+    let mut n = bytes.to_vec();
+    let mut used = Used::default();
+    let mut s = String::with_capacity(81);
+    for k in 0..81 {
+        if !is_set(k) {
+            s.push('.');
+            continue;
+        }
+        let free = used.free(k);
+        if free == 0 {
+            return None;
+        }
+        let mut bits = free;
+        for _ in 0..div_rem(&mut n, free.count_ones()) {
+            bits &= bits - 1;
+        }
+        let bit = bits & bits.wrapping_neg();
+        used.set(k, bit);
+        s.push((b'1' + bit.trailing_zeros() as u8) as char);
+    }
+    // nothing left for a valid encoding
+    if n.iter().any(|&byte| byte != 0) {
+        return None;
+    }
+    Board::new(&s)
+}
+
+// n = n * m + a, `n` is little endian without trailing zeros
+fn mul_add(n: &mut Vec<u8>, m: u32, a: u32) {
+    let mut carry = a;
+    for byte in n.iter_mut() {
+        let v = *byte as u32 * m + carry;
+        *byte = v as u8;
+        carry = v >> 8;
+    }
+    while carry != 0 {
+        n.push(carry as u8);
+        carry >>= 8;
+    }
+}
+// n = n / m, returns n % m
+fn div_rem(n: &mut Vec<u8>, m: u32) -> u32 {
+    let mut rem = 0;
+    for byte in n.iter_mut().rev() {
+        let v = (rem << 8) | *byte as u32;
+        *byte = (v / m) as u8;
+        rem = v % m;
+    }
+    while n.last() == Some(&0) {
+        n.pop();
+    }
+    rem
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::Cell;
+    use crate::testing::{ANSWER, HARD};
+
+    #[test]
+    fn puzzle() {
+        let b = Board::new(HARD).unwrap();
+        let bytes = pack_puzzle(&b).unwrap();
+        assert!(bytes.len() <= 11 + 9);
+        assert_eq!(Some(b), unpack_puzzle(&bytes));
+
+        let empty = Board::new(&".".repeat(81)).unwrap();
+        assert_eq!(vec![0; 11], pack_puzzle(&empty).unwrap());
+        assert_eq!(Some(empty), unpack_puzzle(&[0; 11]));
+
+        // two '8's in the first row
+        assert_eq!(
+            None,
+            pack_puzzle(&Board::new(&HARD.replacen('.', "8", 1)).unwrap())
+        );
+        assert_eq!(None, unpack_puzzle(&bytes[..10]));
+        let mut extra = bytes.clone();
+        extra.push(1);
+        assert_eq!(None, unpack_puzzle(&extra));
+        extra.pop();
+        extra.push(0);
+        assert_eq!(None, unpack_puzzle(&extra));
+    }
+
+    #[test]
+    fn grid() {
+        let ans = Board::new(ANSWER).unwrap();
+        let bytes = pack_grid(&ans).unwrap();
+        assert!(bytes.len() <= 13);
+        assert_eq!(Some(ans), unpack_grid(&bytes));
+        assert_eq!(None, pack_grid(&Board::new(HARD).unwrap()));
+        let mut zeros = bytes.clone();
+        zeros.extend([0, 0]);
+        assert_eq!(None, unpack_grid(&zeros));
+
+        // swap two cells of the first row, columns conflict
+        let mut swapped = ans;
+        swapped.set_cell(Cell::new(0, 0), ans.cell(Cell::new(0, 1)));
+        swapped.set_cell(Cell::new(0, 1), ans.cell(Cell::new(0, 0)));
+        assert_eq!(None, pack_grid(&swapped));
+    }
+
+    #[test]
+    fn big_number() {
+        let mut n = Vec::new();
+        mul_add(&mut n, 9, 8);
+        mul_add(&mut n, 1000, 999);
+        assert_eq!(vec![0x27, 0x23], n);
+        assert_eq!(999, div_rem(&mut n, 1000));
+        assert_eq!(8, div_rem(&mut n, 9));
+        assert_eq!(Vec::<u8>::new(), n);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::Cell;
+use crate::constraint::{restrict, Changed, Constraint, Contradiction};
+
+/// Relation between the numbers of two cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    /// differ by 1, i.e. a white kropki dot
+    Consecutive,
+    /// ratio of 2, i.e. a black kropki dot
+    Double,
+    /// can't differ by 1
+    NonConsecutive,
+    /// neither differ by 1 nor ratio of 2, i.e. no dot under negative constraint
+    Neither,
+    /// the first cell is greater than the second one
+    Greater,
+    /// the first cell is less than the second one
+    Less,
+}
+impl Relation {
+    /// numbers of the other cell compatible with number `n`(1-9) as a bitmap
+    fn compat(self, n: u8) -> u16 {
+        let v = 1u16 << (n - 1);
+        let adjacent = ((v << 1) | (v >> 1)) & 0x1FF;
+        let mut double = 0;
+        if n * 2 <= 9 {
+            double |= 1 << (n * 2 - 1);
+        }
+        if n % 2 == 0 {
+            double |= 1 << (n / 2 - 1);
+        }
+        match self {
+            Self::Consecutive => adjacent,
+            Self::Double => double,
+            Self::NonConsecutive => 0x1FF & !adjacent,
+            Self::Neither => 0x1FF & !(adjacent | double),
+            // numbers of the first cell for `n` of the second one
+            Self::Greater => 0x1FF & !((v << 1) - 1),
+            Self::Less => v - 1,
+        }
+    }
+    /// the relation from the second cell to the first one
+    fn reverse(self) -> Self {
+        match self {
+            Self::Greater => Self::Less,
+            Self::Less => Self::Greater,
+            rel => rel,
+        }
+    }
+}
+
+/// A relation between two cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pair {
+    pub a: Cell,
+    pub b: Cell,
+    pub rel: Relation,
+}
+
+/// Pairwise constraints, e.g. kropki dots, non-consecutive and inequality signs
+///
+/// Text format, one dot or sign per line, `#` begins a comment:
+/// ```text
+/// # white dot between r1c1 and r1c2, black dot between r1c1 and r2c1
+/// r1c1 r1c2 w
+/// r1c1 r2c1 b
+/// # r1c2 is greater than r1c3, r2c1 is less than r3c1
+/// r1c2 > r1c3
+/// r2c1 < r3c1
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pairwise(Vec<Pair>);
+impl Pairwise {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// orthogonal neighbours may not differ by 1
+    pub fn non_consecutive() -> Self {
+        let mut p = Self::new();
+        for (a, b) in Self::neighbours() {
+            p.add(a, b, Relation::NonConsecutive);
+        }
+        p
+    }
+    /// kropki dots from the text format, with `negative` all orthogonal
+    /// neighbours without a dot are neither consecutive nor ratio of 2
+    pub fn kropki(s: &str, negative: bool) -> Option<Self> {
+        let mut p = Self::new();
+        for tokens in entries(s) {
+            let (a, b, rel) = match tokens[..] {
+                [a, b, "w"] | [a, b, "W"] => (a, b, Relation::Consecutive),
+                [a, b, "b"] | [a, b, "B"] => (a, b, Relation::Double),
+                _ => return None,
+            };
+            let (a, b) = (a.parse().ok()?, b.parse().ok()?);
+            if !is_neighbour(a, b) {
+                return None;
+            }
+            p.add(a, b, rel);
+        }
+
+        if negative {
+            for (a, b) in Self::neighbours() {
+                if !p.0.iter().any(|pair| pair.is(a, b)) {
+                    p.add(a, b, Relation::Neither);
+                }
+            }
+        }
+        Some(p)
+    }
+    /// inequality signs from the text format, between adjacent cells
+    pub fn inequalities(s: &str) -> Option<Self> {
+        let mut p = Self::new();
+        for tokens in entries(s) {
+            let (a, b, rel) = match tokens[..] {
+                [a, ">", b] => (a, b, Relation::Greater),
+                [a, "<", b] => (a, b, Relation::Less),
+                _ => return None,
+            };
+            let (a, b) = (a.parse().ok()?, b.parse().ok()?);
+            if !is_neighbour(a, b) {
+                return None;
+            }
+            p.add(a, b, rel);
+        }
+        Some(p)
+    }
+    pub fn add(&mut self, a: Cell, b: Cell, rel: Relation) {
+        self.0.push(Pair { a, b, rel });
+    }
+    pub fn append(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, Pair> {
+        self.0.iter()
+    }
+
+    // prune candidates of `x` which have no compatible number in `y`
+    fn revise(g: &mut Board, x: Cell, y: Cell, rel: Relation) -> Result<Changed, Contradiction> {
+        let mut allowed = 0;
+        let mut bits = g.cell(y).bits();
+        while bits != 0 {
+            allowed |= rel.compat(bits.trailing_zeros() as u8 + 1);
+            bits &= bits - 1;
+        }
+        restrict(g, x, Candidates::from_bits(allowed))
+    }
+
+    // all orthogonal neighbours, each pair once
+    fn neighbours() -> impl Iterator<Item = (Cell, Cell)> {
+        (0..9).flat_map(|i| {
+            (0..8).flat_map(move |j| {
+                vec![
+                    (Cell::new(i, j), Cell::new(i, j + 1)),
+                    (Cell::new(j, i), Cell::new(j + 1, i)),
+                ]
+            })
+        })
+    }
+}
+impl Constraint for Pairwise {
+    fn cells(&self) -> Vec<Cell> {
+        let mut cells = self
+            .0
+            .iter()
+            .flat_map(|pair| vec![pair.a, pair.b])
+            .collect::<Vec<_>>();
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+    /// prune candidates of both cells of every pair by the other one
+    fn propagate(&self, g: &mut Board) -> Result<Changed, Contradiction> {
+        let mut changed = false;
+        for pair in self.0.iter() {
+            changed |= Self::revise(g, pair.a, pair.b, pair.rel)?;
+            changed |= Self::revise(g, pair.b, pair.a, pair.rel.reverse())?;
+        }
+        Ok(changed)
+    }
+}
+impl Pair {
+    fn is(&self, a: Cell, b: Cell) -> bool {
+        (self.a, self.b) == (a, b) || (self.a, self.b) == (b, a)
+    }
+}
+
+// tokens of each line, without comments and empty lines
+fn entries(s: &str) -> impl Iterator<Item = Vec<&str>> {
+    s.lines()
+        .map(|line| line.split('#').next().unwrap())
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|tokens| !tokens.is_empty())
+}
+fn is_neighbour(a: Cell, b: Cell) -> bool {
+    let (dr, dc) = (
+        a.row().max(b.row()) - a.row().min(b.row()),
+        a.col().max(b.col()) - a.col().min(b.col()),
+    );
+    dr + dc == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compat() {
+        assert_eq!(
+            "46",
+            Candidates::from_bits(Relation::Consecutive.compat(5)).to_string()
+        );
+        assert_eq!(
+            "2",
+            Candidates::from_bits(Relation::Consecutive.compat(1)).to_string()
+        );
+        assert_eq!(
+            "28",
+            Candidates::from_bits(Relation::Double.compat(4)).to_string()
+        );
+        assert_eq!(
+            "X",
+            Candidates::from_bits(Relation::Double.compat(7)).to_string()
+        );
+        assert_eq!(
+            "1234579",
+            Candidates::from_bits(Relation::NonConsecutive.compat(7)).to_string()
+        );
+        assert_eq!(
+            "14679",
+            Candidates::from_bits(Relation::Neither.compat(4)).to_string()
+        );
+        assert_eq!(
+            "6789",
+            Candidates::from_bits(Relation::Greater.compat(5)).to_string()
+        );
+        assert_eq!(
+            "X",
+            Candidates::from_bits(Relation::Greater.compat(9)).to_string()
+        );
+        assert_eq!(
+            "12",
+            Candidates::from_bits(Relation::Less.compat(3)).to_string()
+        );
+    }
+
+    #[test]
+    fn kropki() {
+        let p = Pairwise::kropki("# dots\nr1c1 r1c2 w\n\nR2C1 r1c1 b # black\n", false).unwrap();
+        assert_eq!(2, p.iter().count());
+        assert_eq!((Cell::new(1, 0), Relation::Double), (p.0[1].a, p.0[1].rel));
+
+        // 9 rows and 9 cols, 8 pairs each
+        let p = Pairwise::kropki("r1c1 r1c2 w", true).unwrap();
+        assert_eq!(144, p.iter().count());
+        assert_eq!(Relation::Consecutive, p.0[0].rel);
+        assert_eq!(
+            1,
+            p.iter()
+                .filter(|pair| pair.is(Cell::new(0, 0), Cell::new(0, 1)))
+                .count()
+        );
+
+        assert_eq!(None, Pairwise::kropki("r1c1 r2c2 w", false));
+        assert_eq!(None, Pairwise::kropki("r1c1 r1c2 x", false));
+        assert_eq!(None, Pairwise::kropki("r1c1 r1c2", false));
+        assert_eq!(None, Pairwise::kropki("r0c1 r1c1 w", false));
+    }
+
+    #[test]
+    fn inequalities() {
+        let p = Pairwise::inequalities("r1c2 > r1c3 # greater\nr2c1 < r3c1").unwrap();
+        assert_eq!(2, p.iter().count());
+        assert_eq!((Cell::new(1, 0), Relation::Less), (p.0[1].a, p.0[1].rel));
+
+        assert_eq!(None, Pairwise::inequalities("r1c1 > r2c2"));
+        assert_eq!(None, Pairwise::inequalities("r1c1 = r1c2"));
+        assert_eq!(None, Pairwise::inequalities("r1c1 r1c2 w"));
+
+        let mut g = Board::new(&".".repeat(81)).unwrap();
+        g.set_cell(Cell::new(0, 1), Candidates::from('6'));
+        let p = Pairwise::inequalities("r1c1 < r1c2\nr1c2 < r1c3\nr1c3 > r2c3").unwrap();
+        assert_eq!(Ok(true), p.propagate(&mut g));
+        assert_eq!("12345", g.cell(Cell::new(0, 0)).to_string());
+        assert_eq!("789", g.cell(Cell::new(0, 2)).to_string());
+        assert_eq!("12345678", g.cell(Cell::new(1, 2)).to_string());
+    }
+
+    #[test]
+    fn propagate() {
+        let mut g = Board::new(&".".repeat(81)).unwrap();
+        g.set_cell(Cell::new(0, 0), Candidates::from('4'));
+        let p = Pairwise::kropki("r1c1 r1c2 w\nr1c1 r2c1 b", false).unwrap();
+        assert_eq!(Ok(true), p.propagate(&mut g));
+        assert_eq!("35", g.cell(Cell::new(0, 1)).to_string());
+        assert_eq!("28", g.cell(Cell::new(1, 0)).to_string());
+        assert_eq!(Ok(false), p.propagate(&mut g));
+
+        g.set_cell(Cell::new(0, 1), Candidates::from('7'));
+        assert_eq!(Err(Contradiction), p.propagate(&mut g));
+    }
+}
@@ -0,0 +1,361 @@
+//! full-screen play mode of the terminal
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{
+    Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::{cursor, execute, queue, terminal};
+
+use sudoku::cell::Cell;
+use sudoku::game::{Action, Session};
+use sudoku::hint::{self, Hint};
+
+const HELP: &str =
+    "arrows/hjkl move  1-9 enter  0/del clear  p pencil  u/r undo/redo  ? hint  q quit";
+
+/// A game in play
+struct Play {
+    session: Session,
+    cursor: Cell,
+    pencil: bool,
+    hint: Option<Hint>,
+    message: String,
+    // time played before, and when the play started
+    played: Duration,
+    start: Instant,
+    finished: Option<Duration>,
+}
+impl Play {
+    fn new(session: Session) -> Self {
+        Self {
+            cursor: Cell::new(0, 0),
+            pencil: false,
+            hint: None,
+            message: String::new(),
+            played: session.elapsed(),
+            start: Instant::now(),
+            finished: if session.is_complete() {
+                Some(session.elapsed())
+            } else {
+                None
+            },
+            session,
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.finished
+            .unwrap_or_else(|| self.played + self.start.elapsed())
+    }
+
+    // play at the cursor, the timer stops once it's complete
+    fn play(&mut self, action: Action) {
+        if self.finished.is_none() && self.session.play(self.cursor, action) {
+            self.changed();
+        }
+    }
+    fn undo(&mut self) {
+        if self.finished.is_none() && self.session.undo().is_some() {
+            self.changed();
+        }
+    }
+    fn redo(&mut self) {
+        if self.finished.is_none() && self.session.redo().is_some() {
+            self.changed();
+        }
+    }
+    fn changed(&mut self) {
+        self.hint = None;
+        self.message.clear();
+        if self.session.is_complete() {
+            self.finished = Some(self.elapsed());
+            self.message = format!("solved in {}", clock(self.elapsed()));
+        }
+    }
+    // the next logical step, or the answer of the cursor cell if there's none
+    fn hint(&mut self) {
+        if self.finished.is_some() {
+            return;
+        }
+        self.hint = None;
+        self.message = match self.session.mistakes().first() {
+            Some(c) => format!("{} is wrong", c),
+            None => match hint::next(&self.session.board()) {
+                Some(hint) => {
+                    let message = hint.to_string();
+                    self.hint = Some(hint);
+                    message
+                }
+                None => format!(
+                    "no logical step, {} is {}",
+                    self.cursor,
+                    self.session.solution().lucky(self.cursor)
+                ),
+            },
+        };
+    }
+    fn go(&mut self, rows: isize, cols: isize) {
+        let row = (self.cursor.row() as isize + rows).rem_euclid(9);
+        let col = (self.cursor.col() as isize + cols).rem_euclid(9);
+        self.cursor = Cell::new(row as usize, col as usize);
+    }
+
+    // false to quit
+    fn key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::Up | KeyCode::Char('k') => self.go(-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => self.go(1, 0),
+            KeyCode::Left | KeyCode::Char('h') => self.go(0, -1),
+            KeyCode::Right | KeyCode::Char('l') => self.go(0, 1),
+            KeyCode::Char(c @ '1'..='9') if self.pencil => self.play(Action::Mark(c as u8 - b'0')),
+            KeyCode::Char(c @ '1'..='9') => self.play(Action::Enter(c as u8 - b'0')),
+            KeyCode::Char('0') | KeyCode::Char('.') | KeyCode::Backspace | KeyCode::Delete => {
+                self.play(Action::Clear)
+            }
+            KeyCode::Char('p') => self.pencil = !self.pencil,
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('r') => self.redo(),
+            KeyCode::Char('?') => self.hint(),
+            _ => {}
+        }
+        true
+    }
+
+    // draw and take keys until quit
+    fn run(&mut self) -> io::Result<()> {
+        let _screen = Screen::enter()?;
+        let mut out = io::stdout();
+        loop {
+            self.draw(&mut out)?;
+            // redraw every second for the timer
+            if !event::poll(Duration::from_secs(1))? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    // each cell is 5*3 chars, pencil marks like a keypad
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        let bound = format!("  +{}+", ["-".repeat(15).as_str(); 3].join("+"));
+        let mut labels = String::from("   ");
+        for col in 0..9 {
+            labels.push_str(&format!("  {}  ", (b'A' + col) as char));
+            if col % 3 == 2 {
+                labels.push(' ');
+            }
+        }
+        queue!(out, Print(labels), Print("\r\n"))?;
+        for row in 0..9 {
+            if row % 3 == 0 {
+                queue!(out, Print(&bound), Print("\r\n"))?;
+            }
+            for line in 0..3 {
+                let label = if line == 1 {
+                    (b'1' + row as u8) as char
+                } else {
+                    ' '
+                };
+                queue!(out, Print(format!("{} |", label)))?;
+                for col in 0..9 {
+                    self.draw_cell(out, Cell::new(row, col), line)?;
+                    if col % 3 == 2 {
+                        queue!(out, Print("|"))?;
+                    }
+                }
+                queue!(out, Print("\r\n"))?;
+            }
+        }
+        queue!(out, Print(&bound), Print("\r\n\r\n"))?;
+
+        let mode = if self.pencil { "pencil" } else { "number" };
+        queue!(
+            out,
+            Print(format!(
+                "{}  {}  {}\r\n",
+                clock(self.elapsed()),
+                mode,
+                self.cursor
+            )),
+            Print(&self.message),
+            Print("\r\n"),
+            SetAttribute(Attribute::Dim),
+            Print(HELP),
+            SetAttribute(Attribute::Reset),
+        )?;
+        out.flush()
+    }
+    fn draw_cell(&self, out: &mut impl Write, c: Cell, line: usize) -> io::Result<()> {
+        let opts = self.hint.as_ref().map(Hint::options).unwrap_or_default();
+        let bg = if c == self.cursor {
+            Color::DarkGrey
+        } else if opts.is_highlighted(c) {
+            Color::DarkYellow
+        } else {
+            Color::Reset
+        };
+        queue!(out, SetBackgroundColor(bg))?;
+        let s = &self.session;
+        if let Some(n) = s.number(c) {
+            let text = if line == 1 {
+                format!("  {}  ", n)
+            } else {
+                " ".repeat(5)
+            };
+            let fg = if s.is_conflict(c) {
+                Color::Red
+            } else if s.is_given(c) {
+                Color::Reset
+            } else {
+                Color::Blue
+            };
+            if s.is_given(c) {
+                queue!(out, SetAttribute(Attribute::Bold))?;
+            }
+            queue!(
+                out,
+                SetForegroundColor(fg),
+                Print(text),
+                SetAttribute(Attribute::Reset)
+            )?;
+        } else {
+            let marks = s.marks(c);
+            queue!(out, Print(" "))?;
+            for n in line as u8 * 3 + 1..=line as u8 * 3 + 3 {
+                if marks.bits() & 1 << (n - 1) == 0 {
+                    queue!(out, Print(" "))?;
+                    continue;
+                }
+                let fg = if opts.is_eliminated(c, n) {
+                    Color::Red
+                } else if opts.is_highlighted_candidate(c, n) {
+                    Color::Yellow
+                } else {
+                    Color::Grey
+                };
+                queue!(out, SetForegroundColor(fg), Print(n))?;
+            }
+            queue!(out, Print(" "))?;
+        }
+        queue!(
+            out,
+            SetForegroundColor(Color::Reset),
+            SetBackgroundColor(Color::Reset)
+        )
+    }
+}
+
+// the terminal is restored when it's dropped, even on panics
+struct Screen;
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// play the game until quit, the moves and the time played are kept in the
+/// game even if the terminal fails
+pub fn run(session: &mut Session) -> io::Result<()> {
+    let mut play = Play::new(session.clone());
+    let result = play.run();
+    play.session.set_elapsed(play.elapsed());
+    *session = play.session;
+    result
+}
+
+// e.g. "01:05"
+fn clock(d: Duration) -> String {
+    let s = d.as_secs();
+    format!("{:02}:{:02}", s / 60, s % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{ANSWER, HARD};
+    use sudoku::board::Board;
+
+    fn press(play: &mut Play, keys: &str) {
+        for c in keys.chars() {
+            assert!(play.key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)));
+        }
+    }
+
+    #[test]
+    fn keys() {
+        let mut play = Play::new(Session::new(Board::new(HARD).unwrap()).unwrap());
+        // moves wrap around the board
+        press(&mut play, "kh");
+        assert_eq!(Cell::new(8, 8), play.cursor);
+        play.key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        press(&mut play, "l");
+        assert_eq!(Cell::new(0, 0), play.cursor);
+
+        // a given can't be changed
+        press(&mut play, "1");
+        assert_eq!(0, play.session.moves().len());
+        press(&mut play, "l5");
+        assert_eq!(Some(5), play.session.entry(Cell::new(0, 1)));
+        press(&mut play, "p14p");
+        assert!(!play.pencil);
+        assert_eq!(0b1001, play.session.marks(Cell::new(0, 1)).bits());
+        press(&mut play, "0");
+        assert_eq!(None, play.session.entry(Cell::new(0, 1)));
+
+        press(&mut play, "uu");
+        assert_eq!(Some(5), play.session.entry(Cell::new(0, 1)));
+        assert_eq!(0b0001, play.session.marks(Cell::new(0, 1)).bits());
+        play.key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::CONTROL));
+        assert_eq!(0b1001, play.session.marks(Cell::new(0, 1)).bits());
+
+        // 5 is wrong, told before any step
+        press(&mut play, "?");
+        assert_eq!("r1c2 is wrong", play.message);
+        assert!(!play.key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)));
+        assert!(!play.key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+    }
+
+    #[test]
+    fn timer() {
+        // the answer with r1c1 blank
+        let mut session = Session::new(Board::new(&format!(".{}", &ANSWER[1..])).unwrap()).unwrap();
+        session.set_elapsed(Duration::from_secs(65));
+        let mut play = Play::new(session);
+        assert!(play.elapsed() >= Duration::from_secs(65));
+        assert_eq!(None, play.finished);
+
+        press(&mut play, "8");
+        assert_eq!("solved in 01:05", play.message);
+        // stopped once it's solved, no more moves
+        let finished = play.elapsed();
+        press(&mut play, "0u");
+        assert_eq!(finished, play.elapsed());
+        assert_eq!(Some(8), play.session.entry(Cell::new(0, 0)));
+        assert_eq!("61:01", clock(Duration::from_secs(3661)));
+    }
+}
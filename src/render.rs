@@ -0,0 +1,88 @@
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::Cell;
+
+pub mod booklet;
+pub mod svg;
+pub mod terminal;
+pub mod tikz;
+
+/// What to draw besides the numbers of the board
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    /// candidates of unsolved cells as small numbers
+    pub pencil_marks: bool,
+    /// cells with a highlighted background
+    pub cells: Vec<Cell>,
+    /// candidates highlighted as (cell, number 1-9), with `pencil_marks`
+    pub candidates: Vec<(Cell, u8)>,
+    /// candidates struck out as (cell, number 1-9), with `pencil_marks`
+    pub eliminated: Vec<(Cell, u8)>,
+}
+impl Options {
+    pub fn is_highlighted(&self, c: Cell) -> bool {
+        self.cells.contains(&c)
+    }
+    pub fn is_highlighted_candidate(&self, c: Cell, n: u8) -> bool {
+        self.candidates.contains(&(c, n))
+    }
+    pub fn is_eliminated(&self, c: Cell, n: u8) -> bool {
+        self.eliminated.contains(&(c, n))
+    }
+}
+
+/// How a cell is drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Content {
+    /// a number of the puzzle
+    Given(char),
+    /// a number solved by the player or the solver
+    Solved(char),
+    /// candidates of an unsolved cell
+    Marks(Candidates),
+}
+impl Content {
+    /// the cell of `board`, given if it's also solved in `givens`
+    pub fn of(givens: &Board, board: &Board, c: Cell) -> Self {
+        let cs = board.cell(c);
+        if cs.len() != 1 {
+            Self::Marks(cs)
+        } else if givens.cell(c) == cs {
+            Self::Given(cs.lucky())
+        } else {
+            Self::Solved(cs.lucky())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{ANSWER, HARD};
+
+    #[test]
+    fn content() {
+        let givens = Board::new(HARD).unwrap();
+        let mut board = Board::new(ANSWER).unwrap();
+        let count = |board: &Board, f: fn(Content) -> bool| {
+            Cell::all()
+                .filter(|&c| f(Content::of(&givens, board, c)))
+                .count()
+        };
+        assert_eq!(21, count(&board, |x| matches!(x, Content::Given(_))));
+        assert_eq!(60, count(&board, |x| matches!(x, Content::Solved(_))));
+        assert_eq!(0, count(&givens, |x| matches!(x, Content::Solved(_))));
+
+        let (a, b) = (Cell::new(0, 0), Cell::new(0, 1));
+        assert_eq!(Content::Given('8'), Content::of(&givens, &board, a));
+        assert_eq!(Content::Solved('1'), Content::of(&givens, &board, b));
+        // a given changed on the board is drawn as the board's
+        board.set_cell(a, Candidates::from('9'));
+        assert_eq!(Content::Solved('9'), Content::of(&givens, &board, a));
+        board.set_cell(b, Candidates::from_bits(0b1010));
+        assert_eq!(
+            Content::Marks(Candidates::from_bits(0b1010)),
+            Content::of(&givens, &board, b)
+        );
+    }
+}
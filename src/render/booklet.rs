@@ -0,0 +1,227 @@
+use std::fmt::Write;
+
+use super::{svg, Options};
+use crate::board::Board;
+use crate::ripple::Ripple;
+
+/// A4 in points
+const WIDTH: usize = 595;
+const HEIGHT: usize = 842;
+const MARGIN: usize = 36;
+/// space above the puzzles for the title of the booklet
+const HEADER: usize = 40;
+/// space above each board for its title and difficulty
+const LABEL: usize = 22;
+/// answers are smaller, 3 cols and 4 rows in a page
+const ANSWERS_PER_PAGE: usize = 12;
+
+/// A puzzle of the booklet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub title: String,
+    /// difficulty label, e.g. "easy" or a rating
+    pub difficulty: String,
+    pub puzzle: Board,
+    pub answer: Board,
+}
+
+/// Printable booklet, pages of puzzles and then pages of answers, as SVG
+/// pages of A4
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Booklet {
+    title: String,
+    per_page: usize,
+    entries: Vec<Entry>,
+}
+impl Booklet {
+    /// `per_page` puzzles in each page, 1-12
+    pub fn new(title: &str, per_page: usize) -> Option<Self> {
+        if !(1..=12).contains(&per_page) {
+            return None;
+        }
+        Some(Self {
+            title: title.to_string(),
+            per_page,
+            entries: Vec::new(),
+        })
+    }
+    /// add a puzzle with its answer, false if it has no answer
+    pub fn add(&mut self, title: &str, difficulty: &str, puzzle: Board) -> bool {
+        let mut r = Ripple::from_board(puzzle);
+        if !r.do_solve() {
+            return false;
+        }
+        self.entries.push(Entry {
+            title: title.to_string(),
+            difficulty: difficulty.to_string(),
+            puzzle,
+            answer: *r.board(),
+        });
+        true
+    }
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// all pages as SVG documents, puzzles first and answers at the end
+    pub fn pages(&self) -> Vec<String> {
+        let puzzles = self.entries.chunks(self.per_page).collect::<Vec<_>>();
+        let answers = self.entries.chunks(ANSWERS_PER_PAGE).collect::<Vec<_>>();
+        let total = puzzles.len() + answers.len();
+
+        let mut pages = Vec::with_capacity(total);
+        for (k, entries) in puzzles.iter().enumerate() {
+            let boards = entries.iter().enumerate().map(|(i, e)| {
+                let n = k * self.per_page + i + 1;
+                (label(n, e), &e.puzzle, &e.puzzle)
+            });
+            pages.push(Self::page(
+                &self.title,
+                self.per_page,
+                boards,
+                pages.len() + 1,
+                total,
+            ));
+        }
+        for (k, entries) in answers.iter().enumerate() {
+            let boards = entries.iter().enumerate().map(|(i, e)| {
+                let n = k * ANSWERS_PER_PAGE + i + 1;
+                (format!("{}. {}", n, e.title), &e.puzzle, &e.answer)
+            });
+            let title = format!("{} - Answers", self.title);
+            pages.push(Self::page(
+                &title,
+                ANSWERS_PER_PAGE,
+                boards,
+                pages.len() + 1,
+                total,
+            ));
+        }
+        pages
+    }
+
+    // a page of boards in a grid, more cols for more boards per page
+    fn page<'a>(
+        title: &str,
+        per_page: usize,
+        boards: impl Iterator<Item = (String, &'a Board, &'a Board)>,
+        page: usize,
+        total: usize,
+    ) -> String {
+        let cols = match per_page {
+            1 => 1,
+            2..=8 => 2,
+            _ => 3,
+        };
+        let rows = per_page.div_ceil(cols);
+        let slot_w = (WIDTH - MARGIN * 2) / cols;
+        let slot_h = (HEIGHT - MARGIN * 2 - HEADER) / rows;
+        let size = (slot_w - 12).min(slot_h - LABEL - 12);
+        let scale = size as f64 / svg::SIZE as f64;
+
+        let mut s = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="210mm" height="297mm" viewBox="0 0 {} {}">
+<rect x="0" y="0" width="{0}" height="{1}" fill="white"/>
+<text x="{2}" y="{3}" font-family="serif" font-size="22" text-anchor="middle">{4}</text>
+"#,
+            WIDTH,
+            HEIGHT,
+            WIDTH / 2,
+            MARGIN + 22,
+            escape(title)
+        );
+        for (k, (label, givens, board)) in boards.enumerate() {
+            let (col, row) = (k % cols, k / cols);
+            // center the board in the slot
+            let x = MARGIN + col * slot_w + (slot_w - size) / 2;
+            let y = MARGIN + HEADER + row * slot_h + LABEL;
+            let _ = writeln!(
+                s,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}">{}</text>"#,
+                x,
+                y - 6,
+                if cols == 3 { 10 } else { 13 },
+                escape(&label)
+            );
+            let _ = writeln!(
+                s,
+                r#"<g transform="translate({} {}) scale({:.4})">"#,
+                x, y, scale
+            );
+            s.push_str(&svg::group(givens, board, &Options::default()));
+            s.push_str("</g>\n");
+        }
+        let _ = write!(
+            s,
+            r#"<text x="{}" y="{}" font-family="serif" font-size="10" text-anchor="middle">{} / {}</text>
+</svg>
+"#,
+            WIDTH / 2,
+            HEIGHT - MARGIN / 2,
+            page,
+            total
+        );
+        s
+    }
+}
+
+// "1. Title (difficulty)"
+fn label(n: usize, e: &Entry) -> String {
+    if e.difficulty.is_empty() {
+        format!("{}. {}", n, e.title)
+    } else {
+        format!("{}. {} ({})", n, e.title, e.difficulty)
+    }
+}
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candidates::Candidates;
+    use crate::cell::Cell;
+    use crate::testing::{ANSWER, HARD};
+
+    #[test]
+    fn pages() {
+        assert_eq!(None, Booklet::new("Weekly", 0));
+        assert_eq!(None, Booklet::new("Weekly", 13));
+
+        let mut book = Booklet::new("Weekly <1>", 4).unwrap();
+        let b = Board::new(HARD).unwrap();
+        for k in 0..13 {
+            let difficulty = if k < 12 { "hard" } else { "" };
+            assert!(book.add(&format!("Puzzle {}", k + 1), difficulty, b));
+        }
+        let mut no_answer = b;
+        no_answer.set_cell(Cell::new(0, 1), Candidates::from('8'));
+        assert!(!book.add("Broken", "", no_answer));
+        assert_eq!(13, book.entries().len());
+        let ans = Board::new(ANSWER).unwrap();
+        assert_eq!(ans, book.entries()[12].answer);
+
+        // 4 pages of puzzles, 2 pages of answers
+        let pages = book.pages();
+        assert_eq!(6, pages.len());
+        assert_eq!(4, pages[0].matches("<g transform").count());
+        assert_eq!(1, pages[3].matches("<g transform").count());
+        assert_eq!(12, pages[4].matches("<g transform").count());
+        assert_eq!(1, pages[5].matches("<g transform").count());
+        assert!(pages[0].contains("Weekly &lt;1&gt;"));
+        assert!(pages[0].contains("1. Puzzle 1 (hard)"));
+        assert!(pages[3].contains(">13. Puzzle 13<"));
+        assert!(pages[4].contains("Weekly &lt;1&gt; - Answers"));
+        assert!(pages[5].contains(">6 / 6<"));
+
+        // 2*2 boards in a page, the first one at the top left
+        assert!(pages[0].contains(r#"<g transform="translate(42 98) scale(0.6841)">"#));
+        assert!(pages[3].contains(&svg::group(&b, &b, &Options::default())));
+        // answers with the givens of the puzzles
+        assert!(pages[5].contains(&svg::group(&b, &ans, &Options::default())));
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt::Write;
+
+use super::{Content, Options};
+use crate::board::Board;
+use crate::cell::Cell;
+
+/// size of a cell
+const CELL: usize = 40;
+/// space around the grid, for the thick bounds
+const MARGIN: usize = 2;
+/// size of the whole picture
+pub const SIZE: usize = CELL * 9 + MARGIN * 2;
+
+/// SVG picture of the board, numbers solved in `givens` are drawn as givens,
+/// others solved in `board` as solved ones, in another style
+pub fn render(givens: &Board, board: &Board, opts: &Options) -> String {
+    let mut s = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">
+"#,
+        SIZE
+    );
+    s.push_str(&group(givens, board, opts));
+    s.push_str("</svg>\n");
+    s
+}
+
+/// the board as an SVG group at the origin of `SIZE`*`SIZE`, to lay out
+/// more boards in a picture
+pub fn group(givens: &Board, board: &Board, opts: &Options) -> String {
+    let mut s = String::new();
+    s.push_str(r#"<g font-family="sans-serif" text-anchor="middle">"#);
+    s.push('\n');
+    let _ = writeln!(
+        s,
+        r#"<rect x="0" y="0" width="{0}" height="{0}" fill="white"/>"#,
+        SIZE
+    );
+    // highlights below the lines and numbers
+    for &c in opts.cells.iter() {
+        let (x, y) = origin(c);
+        let _ = writeln!(
+            s,
+            r##"<rect x="{}" y="{}" width="{2}" height="{2}" fill="#fff3b0"/>"##,
+            x, y, CELL
+        );
+    }
+
+    for c in Cell::all() {
+        let (x, y) = origin(c);
+        match Content::of(givens, board, c) {
+            Content::Given(n) => {
+                let _ = writeln!(
+                    s,
+                    r#"<text x="{}" y="{}" font-size="28" font-weight="bold" fill="black">{}</text>"#,
+                    x + CELL / 2,
+                    y + 30,
+                    n
+                );
+            }
+            Content::Solved(n) => {
+                let _ = writeln!(
+                    s,
+                    r##"<text x="{}" y="{}" font-size="28" fill="#1565c0">{}</text>"##,
+                    x + CELL / 2,
+                    y + 30,
+                    n
+                );
+            }
+            Content::Marks(cs) if opts.pencil_marks => {
+                for n in 1..=9u8 {
+                    if cs.bits() & (1 << (n - 1)) == 0 {
+                        continue;
+                    }
+                    // 3*3 positions in the cell, like a keypad
+                    let (cx, cy) = (
+                        x + 7 + (n as usize - 1) % 3 * 13,
+                        y + 7 + (n as usize - 1) / 3 * 13,
+                    );
+                    if opts.is_highlighted_candidate(c, n) {
+                        let _ = writeln!(
+                            s,
+                            r##"<circle cx="{}" cy="{}" r="6" fill="#ffab40"/>"##,
+                            cx, cy
+                        );
+                    }
+                    let _ = writeln!(
+                        s,
+                        r##"<text x="{}" y="{}" font-size="11" fill="#555555">{}</text>"##,
+                        cx,
+                        cy + 4,
+                        n
+                    );
+                    if opts.is_eliminated(c, n) {
+                        let _ = writeln!(
+                            s,
+                            r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#d32f2f" stroke-width="1.5"/>"##,
+                            cx - 5,
+                            cy + 5,
+                            cx + 5,
+                            cy - 5
+                        );
+                    }
+                }
+            }
+            Content::Marks(_) => {}
+        }
+    }
+
+    // thin lines first, thick ones of blocks over them
+    let (thin, thick) = (0..=9).partition::<Vec<_>, _>(|k| k % 3 != 0);
+    for k in thin.into_iter().chain(thick) {
+        let (p, width) = (MARGIN + k * CELL, if k % 3 == 0 { 3 } else { 1 });
+        let _ = writeln!(
+            s,
+            r#"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}" stroke="black" stroke-width="{3}"/>"#,
+            p,
+            MARGIN - 1,
+            SIZE - MARGIN + 1,
+            width
+        );
+        let _ = writeln!(
+            s,
+            r#"<line x1="{1}" y1="{0}" x2="{2}" y2="{0}" stroke="black" stroke-width="{3}"/>"#,
+            p,
+            MARGIN - 1,
+            SIZE - MARGIN + 1,
+            width
+        );
+    }
+    s.push_str("</g>\n");
+    s
+}
+
+// top left corner of the cell
+fn origin(c: Cell) -> (usize, usize) {
+    (MARGIN + c.col() * CELL, MARGIN + c.row() * CELL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candidates::Candidates;
+    use crate::testing::{ANSWER, HARD};
+
+    #[test]
+    fn puzzle() {
+        let b = Board::new(HARD).unwrap();
+        let opts = Options::default();
+        let svg = render(&b, &b, &opts);
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="364" height="364""#)
+        );
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(&group(&b, &b, &opts)));
+        // in the middle of r1c1
+        assert!(svg.contains(
+            r#"<text x="22" y="32" font-size="28" font-weight="bold" fill="black">8</text>"#
+        ));
+        assert_eq!(0, svg.matches("font-size=\"11\"").count());
+
+        // thick lines of blocks over the thin ones
+        let widths = svg
+            .lines()
+            .filter(|line| line.starts_with("<line"))
+            .map(|line| line.contains("stroke-width=\"3\""))
+            .collect::<Vec<_>>();
+        assert_eq!(20, widths.len());
+        assert_eq!(vec![false; 12], widths[..12]);
+        assert_eq!(vec![true; 8], widths[12..]);
+    }
+
+    #[test]
+    fn solution() {
+        let b = Board::new(HARD).unwrap();
+        let ans = Board::new(ANSWER).unwrap();
+        let svg = render(&b, &ans, &Options::default());
+        assert!(svg.contains(r##"<text x="62" y="32" font-size="28" fill="#1565c0">1</text>"##));
+        assert!(svg.contains(r##"<text x="342" y="352" font-size="28" fill="#1565c0">2</text>"##));
+        assert!(!svg.contains("#fff3b0"));
+    }
+
+    #[test]
+    fn pencil_marks() {
+        let mut b = Board::new(HARD).unwrap();
+        b.set_cell(Cell::new(0, 1), Candidates::from_bits(0b101011));
+        let opts = Options {
+            pencil_marks: true,
+            cells: vec![Cell::new(0, 1)],
+            candidates: vec![(Cell::new(0, 1), 6), (Cell::new(0, 1), 5)],
+            eliminated: vec![(Cell::new(0, 1), 2)],
+        };
+        let svg = render(&b, &b, &opts);
+        assert_eq!(59 * 9 + 4, svg.matches("font-size=\"11\"").count());
+        // highlights below the numbers
+        let highlight = svg.find(r##"<rect x="42" y="2" width="40" height="40" fill="#fff3b0"/>"##);
+        assert!(highlight.unwrap() < svg.find("<text").unwrap());
+        // 5 isn't a candidate
+        assert_eq!(1, svg.matches("<circle").count());
+        assert!(svg.contains(r##"<circle cx="75" cy="22" r="6""##));
+        assert_eq!(1, svg.matches("#d32f2f").count());
+    }
+}
@@ -0,0 +1,100 @@
+use super::Content;
+use crate::board::Board;
+use crate::cell::Cell;
+
+const BOLD: &str = "\x1b[1m";
+const BLUE: &str = "\x1b[34m";
+const RED: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+/// the board with labels of the README, cols as A-I and rows as 1-9, numbers
+/// solved in `givens` are givens, others solved in `board` are solved ones.
+/// With `color`, givens are bold, solved numbers are blue, and numbers
+/// conflicting with a peer are red.
+/// ```text
+///     A B C   D E F   G H I
+///   +-----------------------+
+/// 1 | 8     |       |       |
+/// 2 |     3 | 6     |       |
+/// ...
+///   +-----------------------+
+/// ```
+pub fn render(givens: &Board, board: &Board, color: bool) -> String {
+    let bound = "  +-------+-------+-------+\n";
+    let mut s = String::from("    A B C   D E F   G H I\n");
+    for row in 0..9 {
+        if row % 3 == 0 {
+            s.push_str(bound);
+        }
+        s.push_str(&format!("{} |", row + 1));
+        for col in 0..9 {
+            let c = Cell::new(row, col);
+            s.push(' ');
+            let (n, style) = match Content::of(givens, board, c) {
+                Content::Given(n) => (n, BOLD),
+                Content::Solved(n) => (n, BLUE),
+                Content::Marks(_) => (' ', ""),
+            };
+            if color && n != ' ' {
+                let style = if is_conflict(board, c) { RED } else { style };
+                s.push_str(&format!("{}{}{}", style, n, RESET));
+            } else {
+                s.push(n);
+            }
+            if col % 3 == 2 {
+                s.push_str(" |");
+            }
+        }
+        s.push('\n');
+    }
+    s.push_str(bound);
+    s
+}
+
+// the solved cell shares its number with a peer
+fn is_conflict(b: &Board, c: Cell) -> bool {
+    b.peers_of(c).iter().any(|&p| b.cell(p) == b.cell(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candidates::Candidates;
+    use crate::testing::{ANSWER, HARD};
+
+    #[test]
+    fn plain() {
+        let b = Board::new(HARD).unwrap();
+        let s = render(&b, &b, false);
+        let lines = s.lines().collect::<Vec<_>>();
+        assert_eq!(14, lines.len());
+        assert_eq!("    A B C   D E F   G H I", lines[0]);
+        assert_eq!("  +-------+-------+-------+", lines[1]);
+        assert_eq!("1 | 8     |       |       |", lines[2]);
+        assert_eq!("2 |     3 | 6     |       |", lines[3]);
+        assert_eq!("9 |   9   |       | 4     |", lines[12]);
+        assert!(!s.contains('\x1b'));
+
+        // solved numbers are the same as givens without colors
+        let s = render(&b, &Board::new(ANSWER).unwrap(), false);
+        assert_eq!(Some("1 | 8 1 2 | 7 5 3 | 6 4 9 |"), s.lines().nth(2));
+    }
+
+    #[test]
+    fn color() {
+        let b = Board::new(HARD).unwrap();
+        let mut g = b;
+        g.set_cell(Cell::new(0, 1), Candidates::from('1'));
+        // conflicts with the given '8' of r1c1 and r8c3
+        g.set_cell(Cell::new(0, 2), Candidates::from('8'));
+        let s = render(&b, &g, true);
+        let first = s.lines().nth(2).unwrap();
+        assert_eq!(
+            "1 | \x1b[1;31m8\x1b[0m \x1b[34m1\x1b[0m \x1b[1;31m8\x1b[0m |       |       |",
+            first
+        );
+        assert_eq!(21 - 2, s.matches(BOLD).count());
+        assert_eq!(3, s.matches(RED).count());
+        assert_eq!(1, s.matches(BLUE).count());
+    }
+}
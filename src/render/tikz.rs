@@ -0,0 +1,149 @@
+use std::fmt::Write;
+
+use super::{Content, Options};
+use crate::board::Board;
+use crate::cell::Cell;
+
+/// TikZ picture of the board, one unit for a cell, numbers solved in `givens`
+/// are drawn as givens, others solved in `board` as solved ones, in blue.
+/// Highlighted cells and candidates of `opts` annotate a hint, eliminated
+/// candidates are struck out in red.
+pub fn picture(givens: &Board, board: &Board, opts: &Options) -> String {
+    let mut s = String::from("\\begin{tikzpicture}[scale=0.6]\n");
+    for &c in opts.cells.iter() {
+        let (x, y) = origin(c);
+        let _ = writeln!(
+            s,
+            "  \\fill[yellow!30] ({},{}) rectangle ({},{});",
+            x,
+            y,
+            x + 1,
+            y + 1
+        );
+    }
+
+    for c in Cell::all() {
+        let (x, y) = origin(c);
+        match Content::of(givens, board, c) {
+            Content::Given(n) => {
+                let _ = writeln!(
+                    s,
+                    "  \\node[font=\\Large\\bfseries] at ({}.5,{}.5) {{{}}};",
+                    x, y, n
+                );
+            }
+            Content::Solved(n) => {
+                let _ = writeln!(
+                    s,
+                    "  \\node[font=\\Large,text=blue!70!black] at ({}.5,{}.5) {{{}}};",
+                    x, y, n
+                );
+            }
+            Content::Marks(cs) if opts.pencil_marks => {
+                for n in 1..=9u8 {
+                    if cs.bits() & (1 << (n - 1)) == 0 {
+                        continue;
+                    }
+                    // 3*3 positions in the cell, like a keypad, from the top
+                    let k = (n - 1) as usize;
+                    let (cx, cy) = (x * 6 + 1 + k % 3 * 2, y * 6 + 5 - k / 3 * 2);
+                    let style = if opts.is_highlighted_candidate(c, n) {
+                        "font=\\tiny,circle,fill=orange!60,inner sep=0.5pt"
+                    } else {
+                        "font=\\tiny,text=black!60"
+                    };
+                    let _ = writeln!(
+                        s,
+                        "  \\node[{}] at ({},{}) {{{}}};",
+                        style,
+                        sixth(cx),
+                        sixth(cy),
+                        n
+                    );
+                    if opts.is_eliminated(c, n) {
+                        let _ = writeln!(
+                            s,
+                            "  \\draw[red,thick] ({},{}) -- ({},{});",
+                            sixth(cx - 1),
+                            sixth(cy - 1),
+                            sixth(cx + 1),
+                            sixth(cy + 1)
+                        );
+                    }
+                }
+            }
+            Content::Marks(_) => {}
+        }
+    }
+
+    s.push_str("  \\draw[step=1,thin] (0,0) grid (9,9);\n");
+    s.push_str("  \\draw[step=3,very thick] (0,0) grid (9,9);\n");
+    s.push_str("\\end{tikzpicture}\n");
+    s
+}
+
+/// a standalone LaTeX document of the picture
+pub fn document(givens: &Board, board: &Board, opts: &Options) -> String {
+    format!(
+        "\\documentclass[tikz]{{standalone}}\n\\begin{{document}}\n{}\\end{{document}}\n",
+        picture(givens, board, opts)
+    )
+}
+
+// bottom left corner of the cell, rows go down from the top
+fn origin(c: Cell) -> (usize, usize) {
+    (c.col(), 8 - c.row())
+}
+// `n`/6 with 3 decimals, e.g. "1.167"
+fn sixth(n: usize) -> String {
+    format!("{:.3}", n as f64 / 6.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candidates::Candidates;
+    use crate::testing::{ANSWER, HARD};
+
+    #[test]
+    fn picture() {
+        let b = Board::new(HARD).unwrap();
+        let tex = super::picture(&b, &b, &Options::default());
+        assert!(tex.starts_with("\\begin{tikzpicture}"));
+        // r1c1 is at the top left
+        assert!(tex.contains("\\node[font=\\Large\\bfseries] at (0.5,8.5) {8};"));
+        assert!(tex.contains("at (3.5,7.5) {6}"));
+        // the grid over the numbers
+        assert!(
+            tex.ends_with("  \\draw[step=3,very thick] (0,0) grid (9,9);\n\\end{tikzpicture}\n")
+        );
+        let ans = Board::new(ANSWER).unwrap();
+        let solved = super::picture(&b, &ans, &Options::default());
+        assert!(solved.contains("\\node[font=\\Large,text=blue!70!black] at (1.5,8.5) {1};"));
+        assert!(solved.contains("\\node[font=\\Large,text=blue!70!black] at (8.5,0.5) {2};"));
+
+        let doc = document(&b, &b, &Options::default());
+        assert!(doc.starts_with("\\documentclass[tikz]{standalone}"));
+        assert!(doc.contains(&tex));
+    }
+
+    #[test]
+    fn hint() {
+        let mut b = Board::new(HARD).unwrap();
+        b.set_cell(Cell::new(0, 1), Candidates::from_bits(0b101011));
+        let opts = Options {
+            pencil_marks: true,
+            cells: vec![Cell::new(0, 1), Cell::new(1, 1)],
+            candidates: vec![(Cell::new(0, 1), 6)],
+            eliminated: vec![(Cell::new(0, 1), 1), (Cell::new(0, 1), 2)],
+        };
+        let tex = super::picture(&b, &b, &opts);
+        assert_eq!(2, tex.matches("\\fill[yellow!30]").count());
+        assert!(tex.contains("\\fill[yellow!30] (1,8) rectangle (2,9);"));
+        assert_eq!(59 * 9 + 4, tex.matches("\\tiny").count());
+        assert_eq!(1, tex.matches("fill=orange!60").count());
+        assert_eq!(2, tex.matches("\\draw[red,thick]").count());
+        // 1 at the top left of r1c2
+        assert!(tex.contains("at (1.167,8.833) {1}"));
+    }
+}
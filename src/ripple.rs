@@ -1,16 +1,46 @@
 use std::convert::From;
 use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::board::*;
 use crate::candidates::*;
+use crate::cell::Cell;
+use crate::constraint::{Constraint, Contradiction};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
 pub struct Ripple {
     g: Board,
+    // with their cells, taken once as the solver needs them on every pass
+    constraints: Vec<(Arc<dyn Constraint>, Vec<Cell>)>,
     // stats
     n_try: usize,
     n_triplex: usize,
 }
+/// Statistics of a search
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// guesses made while backtracking
+    pub tries: usize,
+    /// triplexes found while backtracking
+    pub triplexes: usize,
+}
+impl std::ops::AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.tries += other.tries;
+        self.triplexes += other.triplexes;
+    }
+}
+/// Output of the solver, the solution as 81 numbers if solved
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Answer {
+    pub solution: Option<String>,
+    pub stats: Stats,
+}
 struct Hint {
     first_unsolved_row: usize,
     first_unsolved_col: usize,
@@ -19,25 +49,63 @@ impl Ripple {
     pub fn new(s: &str) -> Option<Self> {
         Some(Self {
             g: Board::new(s)?,
+            constraints: Vec::new(),
             n_try: 0,
             n_triplex: 0,
         })
     }
+    /// solver of a prepared board, e.g. with variant rules
+    pub fn from_board(g: Board) -> Self {
+        Self {
+            g,
+            constraints: Vec::new(),
+            n_try: 0,
+            n_triplex: 0,
+        }
+    }
+    /// add a constraint beyond peers, e.g. kropki dots or a thermometer
+    pub fn add_constraint<C: Constraint + 'static>(&mut self, c: C) {
+        self.push_constraint(Arc::new(c));
+    }
+    fn push_constraint(&mut self, c: Arc<dyn Constraint>) {
+        let cells = c.cells();
+        self.constraints.push((c, cells));
+    }
+    pub fn board(&self) -> &Board {
+        &self.g
+    }
+    pub fn stats(&self) -> Stats {
+        Stats {
+            tries: self.n_try,
+            triplexes: self.n_triplex,
+        }
+    }
+    /// the solution if all cells are solved, with stats of the search
+    pub fn answer(&self) -> Answer {
+        let done = Cell::all().all(|c| self.g.is_done(c));
+        Answer {
+            solution: if done {
+                Some(self.g.iter().flatten().map(|c| c.lucky()).collect())
+            } else {
+                None
+            },
+            stats: self.stats(),
+        }
+    }
     pub fn solve(s: &str) -> Option<String> {
         let mut b = Self {
             g: Board::new(s)?,
+            constraints: Vec::new(),
             n_try: 0,
             n_triplex: 0,
         };
 
         if b.do_solve() {
             let mut s = String::with_capacity(81);
-            for i in 0..9 {
-                for j in 0..9 {
-                    // CAUTION: `to_string().chars().nth(0).unwrap()` takes 3000 ns...
-                    // s.push(b.g[i][j].to_string().chars().nth(0).unwrap());
-                    s.push(b.g.lucky(i, j));
-                }
+            for c in Cell::all() {
+                // CAUTION: `to_string().chars().nth(0).unwrap()` takes 3000 ns...
+                // s.push(b.g[i][j].to_string().chars().nth(0).unwrap());
+                s.push(b.g.lucky(c));
             }
             Some(s)
         } else {
@@ -47,6 +115,7 @@ impl Ripple {
     pub fn solve_arr(puzzle: &mut [[char; 9]; 9]) -> bool {
         let mut b = Self {
             g: Board::from(*puzzle),
+            constraints: Vec::new(),
             n_try: 0,
             n_triplex: 0,
         };
@@ -60,38 +129,77 @@ impl Ripple {
     }
     pub fn do_solve(&mut self) -> bool {
         // init
-        for i in 0..9 {
-            for j in 0..9 {
-                if self.g.is_done(i, j) && !Self::ripple(&mut self.g, i, j) {
-                    return false;
-                }
-            }
+        if !Self::ripple_all(&mut self.g) {
+            return false;
         }
+        let mut g = self.g;
+        if !self.constrain(&mut g) {
+            return false;
+        }
+        self.g = g;
         // println!("init done:\n{}", self);
 
         self.backtrack(&mut self.g.clone())
     }
 
-    fn ripple(g: &mut Board, i: usize, j: usize) -> bool {
-        let cs = g.cell(i, j);
-        for &(row, col) in Board::peers_of(i, j) {
-            let (row, col) = (row as usize, col as usize);
-            if g.cell(row, col) == cs {
+    /// number of answers, counting stops at `limit`, the first answer is kept
+    /// as the board
+    pub fn count(&mut self, limit: usize) -> usize {
+        let mut g = self.g;
+        if limit == 0 || !Self::ripple_all(&mut g) || !self.constrain(&mut g) {
+            return 0;
+        }
+        let mut n = 0;
+        self.count_from(&mut g, limit, &mut n);
+        n
+    }
+
+    /// ripple all determined cells to their peers, false if any conflict or a
+    /// cell of no candidate
+    pub(crate) fn ripple_all(g: &mut Board) -> bool {
+        Cell::all().all(|c| g.cell(c).is_valid() && (!g.is_done(c) || Self::ripple(g, c)))
+    }
+    fn ripple(g: &mut Board, c: Cell) -> bool {
+        let cs = g.cell(c);
+        for &p in g.peers_of(c) {
+            if g.cell(p) == cs {
                 return false;
             }
-            if g.is_done(row, col) {
+            if g.is_done(p) {
                 continue;
             }
-            g.substract(row, col, cs);
-            if g.is_done(row, col) {
+            g.substract(p, cs);
+            if g.is_done(p) {
                 // find a determined cell
-                if !Self::ripple(g, row, col) {
+                if !Self::ripple(g, p) {
                     return false;
                 }
             }
         }
         true
     }
+    // apply constraints until nothing changes, ripple cells solved by them
+    fn constrain(&self, g: &mut Board) -> bool {
+        loop {
+            let mut changed = false;
+            for (c, cells) in self.constraints.iter() {
+                let g0 = *g;
+                match c.propagate(g) {
+                    Err(Contradiction) => return false,
+                    Ok(false) => continue,
+                    Ok(true) => changed = true,
+                }
+                for &cell in cells.iter() {
+                    if g.is_done(cell) && !g0.is_done(cell) && !Self::ripple(g, cell) {
+                        return false;
+                    }
+                }
+            }
+            if !changed {
+                return true;
+            }
+        }
+    }
     fn backtrack(&mut self, g0: &mut Board) -> bool {
         let (row, col, ca, hint) = Self::next_least_unsolved(g0);
         // all cell is done, copy back the result to self.g
@@ -110,8 +218,11 @@ impl Ripple {
         for c in ca.iter() {
             self.n_try += 1;
             // make a guess
-            g.set_cell(row, col, c);
-            if Self::ripple(&mut g, row, col) && self.backtrack(&mut g) {
+            g.set_cell(Cell::new(row, col), c);
+            if Self::ripple(&mut g, Cell::new(row, col))
+                && self.constrain(&mut g)
+                && self.backtrack(&mut g)
+            {
                 return true;
             }
             // rollback
@@ -120,6 +231,33 @@ impl Ripple {
 
         false
     }
+    // as `backtrack`, but goes on after an answer until `limit` answers
+    fn count_from(&mut self, g0: &mut Board, limit: usize, n: &mut usize) {
+        let (row, col, ca, hint) = Self::next_least_unsolved(g0);
+        if ca.is_done() {
+            if *n == 0 {
+                self.g = *g0;
+            }
+            *n += 1;
+            return;
+        }
+        if !self.triplex(g0, hint) {
+            return;
+        }
+
+        let mut g = *g0;
+        for c in ca.iter() {
+            self.n_try += 1;
+            g.set_cell(Cell::new(row, col), c);
+            if Self::ripple(&mut g, Cell::new(row, col)) && self.constrain(&mut g) {
+                self.count_from(&mut g, limit, n);
+                if *n >= limit {
+                    return;
+                }
+            }
+            g = *g0;
+        }
+    }
     fn next_least_unsolved(g: &Board) -> (usize, usize, Candidates, Hint) {
         let mut min_row = 0;
         let mut min_col = 0;
@@ -156,14 +294,18 @@ impl Ripple {
                 }
             }
         }
-        (min_row, min_col, g.cell(min_row, min_col), hint)
+        (min_row, min_col, g.cell(Cell::new(min_row, min_col)), hint)
     }
     fn triplex(&mut self, b: &mut Board, hint: Hint) -> bool {
         for i in 0..9 {
             for g in (0..9).step_by(3) {
                 // row
                 if i >= hint.first_unsolved_row {
-                    let (ca1, ca2, ca3) = (b.cell(i, g), b.cell(i, g + 1), b.cell(i, g + 2));
+                    let (ca1, ca2, ca3) = (
+                        b.cell(Cell::new(i, g)),
+                        b.cell(Cell::new(i, g + 1)),
+                        b.cell(Cell::new(i, g + 2)),
+                    );
                     if !(ca1.is_done() || ca2.is_done() || ca3.is_done())
                         && ca1.len() <= 3
                         && ca2.len() <= 3
@@ -183,7 +325,11 @@ impl Ripple {
 
                 // col
                 if i >= hint.first_unsolved_col {
-                    let (ca1, ca2, ca3) = (b.cell(g, i), b.cell(g + 1, i), b.cell(g + 2, i));
+                    let (ca1, ca2, ca3) = (
+                        b.cell(Cell::new(g, i)),
+                        b.cell(Cell::new(g + 1, i)),
+                        b.cell(Cell::new(g + 2, i)),
+                    );
                     if !(ca1.is_done() || ca2.is_done() || ca3.is_done())
                         && ca1.len() <= 3
                         && ca2.len() <= 3
@@ -210,7 +356,7 @@ impl Ripple {
             if g <= c || c < g + 3 {
                 continue;
             }
-            if !Self::eliminate_multi(b, row, c, uc) {
+            if !Self::eliminate_multi(b, Cell::new(row, c), uc) {
                 return false;
             }
         }
@@ -221,7 +367,7 @@ impl Ripple {
             if row <= r || r < row + 3 {
                 continue;
             }
-            if !Self::eliminate_multi(b, r, col, uc) {
+            if !Self::eliminate_multi(b, Cell::new(r, col), uc) {
                 return false;
             }
         }
@@ -235,7 +381,7 @@ impl Ripple {
                     continue;
                 }
 
-                if !Self::eliminate_multi(b, r, c, uc) {
+                if !Self::eliminate_multi(b, Cell::new(r, c), uc) {
                     return false;
                 }
             }
@@ -250,41 +396,150 @@ impl Ripple {
                     continue;
                 }
 
-                if !Self::eliminate_multi(b, r, c, uc) {
+                if !Self::eliminate_multi(b, Cell::new(r, c), uc) {
                     return false;
                 }
             }
         }
         true
     }
-    fn eliminate_multi(b: &mut Board, row: usize, col: usize, rc: Candidates) -> bool {
-        let mut ca = b.cell(row, col);
+    fn eliminate_multi(b: &mut Board, cell: Cell, rc: Candidates) -> bool {
+        let mut ca = b.cell(cell);
         ca.substract(&rc);
         if !ca.is_valid() {
             return false;
         }
 
-        if ca == b.cell(row, col) {
+        if ca == b.cell(cell) {
             return true;
         } else {
-            b.set_cell(row, col, ca);
+            b.set_cell(cell, ca);
         }
 
-        if ca.is_done() && !Self::ripple(b, row, col) {
+        if ca.is_done() && !Self::ripple(b, cell) {
             return false;
         }
         true
     }
 }
+impl PartialEq for Ripple {
+    fn eq(&self, other: &Self) -> bool {
+        self.g == other.g
+            && self.n_try == other.n_try
+            && self.n_triplex == other.n_triplex
+            && self.constraints.len() == other.constraints.len()
+            && self
+                .constraints
+                .iter()
+                .zip(other.constraints.iter())
+                .all(|((c1, _), (c2, _))| Arc::ptr_eq(c1, c2))
+    }
+}
+impl Eq for Ripple {}
 impl fmt::Display for Ripple {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.g.fmt(f)
     }
 }
 
+/// puzzles taken by a thread at a time
+const BATCH_CHUNK: usize = 64;
+
+/// solve the puzzles with `threads` threads, all cores for 0, answers are in
+/// the order of the puzzles.
+pub fn solve_batch(puzzles: &[Board], threads: usize) -> Vec<Answer> {
+    par_map(puzzles, threads, |g| {
+        let mut r = Ripple::from_board(*g);
+        if r.do_solve() {
+            r.answer()
+        } else {
+            Answer {
+                solution: None,
+                stats: r.stats(),
+            }
+        }
+    })
+}
+
+/// Result of a puzzle counted in a batch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    /// the first answer found
+    pub answer: Answer,
+    /// number of answers, up to the limit
+    pub solutions: usize,
+    pub time: Duration,
+}
+
+/// count answers of the puzzles up to `limit`, like `solve_batch`
+pub fn count_batch(puzzles: &[Board], limit: usize, threads: usize) -> Vec<Report> {
+    par_map(puzzles, threads, |g| {
+        let start = Instant::now();
+        let mut r = Ripple::from_board(*g);
+        let solutions = r.count(limit);
+        let mut answer = r.answer();
+        if solutions == 0 {
+            answer.solution = None;
+        }
+        Report {
+            answer,
+            solutions,
+            time: start.elapsed(),
+        }
+    })
+}
+
+// map the puzzles in order with `threads` threads, all cores for 0.
+// Threads take chunks of puzzles from a shared counter until all are taken.
+fn par_map<T, F>(puzzles: &[Board], threads: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Board) -> T + Sync,
+{
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(puzzles.len().div_ceil(BATCH_CHUNK))
+    .max(1);
+    if threads == 1 {
+        return puzzles.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let chunks = Mutex::new(Vec::new());
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| loop {
+                let start = next.fetch_add(BATCH_CHUNK, Ordering::Relaxed);
+                if start >= puzzles.len() {
+                    break;
+                }
+                let end = (start + BATCH_CHUNK).min(puzzles.len());
+                let results = puzzles[start..end].iter().map(&f).collect::<Vec<_>>();
+                chunks.lock().unwrap().push((start, results));
+            });
+        }
+    });
+    let mut chunks = chunks.into_inner().unwrap();
+    chunks.sort_unstable_by_key(|&(start, _)| start);
+    chunks
+        .into_iter()
+        .flat_map(|(_, results)| results)
+        .collect()
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::needless_borrow)]
 mod tests {
     use super::*;
+    use crate::constraint::{restrict, Arrow, Changed, Line, Sandwich, Thermo};
+    use crate::pairwise::Pairwise;
+    use crate::variant::Variant;
+
+    fn cells(path: &[(usize, usize)]) -> Vec<Cell> {
+        path.iter().map(|&(r, c)| Cell::new(r, c)).collect()
+    }
 
     #[test]
     fn basics() {
@@ -409,6 +664,389 @@ mod tests {
         assert_eq!(true, b.do_solve());
     }
 
+    fn assert_valid(b: &Ripple) {
+        for c in Cell::all() {
+            assert_eq!(true, b.g.is_done(c));
+            for &p in b.g.peers_of(c) {
+                assert_ne!(b.g.cell(c), b.g.cell(p));
+            }
+        }
+    }
+
+    #[test]
+    fn anti_knight() {
+        let mut g = Board::new(&".".repeat(81)).unwrap();
+        g.set_variant(Variant::ANTI_KNIGHT);
+        let mut b = Ripple::from_board(g);
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+
+        // the only classic answer of `no_try` breaks the anti-knight rule
+        let s = "
+		5.28.691.
+		8.1......
+		3....1.25
+		6.9.1...4
+		...659...
+		1...2.7.9
+		43.1....2
+		......1.7
+		.179.45..
+		";
+        let mut g = Board::new(s).unwrap();
+        g.set_variant(Variant::ANTI_KNIGHT);
+        assert_eq!(false, Ripple::from_board(g).do_solve());
+    }
+
+    #[test]
+    fn anti_king() {
+        let mut g = Board::new(&".".repeat(81)).unwrap();
+        g.set_variant(Variant::ANTI_KING | Variant::ANTI_KNIGHT);
+        let mut b = Ripple::from_board(g);
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+    }
+
+    #[test]
+    fn disjoint_center_dot() {
+        let mut g = Board::new(&".".repeat(81)).unwrap();
+        g.set_variant(Variant::DISJOINT | Variant::CENTER_DOT);
+        let mut b = Ripple::from_board(g);
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+
+        // centres of two blocks can't be both 5
+        let mut g = Board::new(&".".repeat(81)).unwrap();
+        g.set_cell(Cell::new(1, 1), Candidates::from('5'));
+        g.set_cell(Cell::new(4, 4), Candidates::from('5'));
+        assert_eq!(true, Ripple::from_board(g).do_solve());
+        g.set_variant(Variant::CENTER_DOT);
+        assert_eq!(false, Ripple::from_board(g).do_solve());
+    }
+
+    #[test]
+    fn non_consecutive() {
+        let mut b = Ripple::new(&".".repeat(81)).unwrap();
+        let pairs = Pairwise::non_consecutive();
+        b.add_constraint(pairs.clone());
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+        for pair in pairs.iter() {
+            let x = b.g.lucky(pair.a) as i8;
+            let y = b.g.lucky(pair.b) as i8;
+            assert_ne!(1, (x - y).abs());
+        }
+    }
+
+    #[test]
+    fn kropki() {
+        let solved = "
+		123456789
+		457389162
+		869271453
+		372594618
+		581762394
+		694813527
+		715948236
+		248635971
+		936127845
+		";
+        let ans = Board::new(solved).unwrap();
+        // all dots of the answer, then solve the empty board with them
+        let mut dots = String::new();
+        for i in 0..9 {
+            for j in 0..9 {
+                for &(r, c) in [(i, j + 1), (i + 1, j)]
+                    .iter()
+                    .filter(|&&(r, c)| r < 9 && c < 9)
+                {
+                    let (x, y) = (
+                        ans.lucky(Cell::new(i, j)) as u8 - b'0',
+                        ans.lucky(Cell::new(r, c)) as u8 - b'0',
+                    );
+                    let kind = if x + 1 == y || y + 1 == x {
+                        'w'
+                    } else if x * 2 == y || y * 2 == x {
+                        'b'
+                    } else {
+                        continue;
+                    };
+                    dots.push_str(&format!(
+                        "r{}c{} r{}c{} {}\n",
+                        i + 1,
+                        j + 1,
+                        r + 1,
+                        c + 1,
+                        kind
+                    ));
+                }
+            }
+        }
+        let mut b = Ripple::new(&".".repeat(81)).unwrap();
+        let pairs = Pairwise::kropki(&dots, true).unwrap();
+        b.add_constraint(pairs.clone());
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+        // all dots are satisfied by the answer
+        assert_eq!(Ok(false), pairs.propagate(&mut b.g.clone()));
+    }
+
+    #[test]
+    fn thermo_arrow_sandwich() {
+        let mut b = Ripple::new(&".".repeat(81)).unwrap();
+        let constraints: Vec<Arc<dyn Constraint>> = vec![
+            Arc::new(Thermo::new(&cells(&[(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)])).unwrap()),
+            Arc::new(Thermo::new(&cells(&[(8, 0), (8, 1), (8, 2)])).unwrap()),
+            Arc::new(Arrow::new(Cell::new(4, 0), &cells(&[(5, 0), (6, 0), (7, 1)])).unwrap()),
+            Arc::new(Arrow::new(Cell::new(2, 6), &cells(&[(3, 7)])).unwrap()),
+            Arc::new(Sandwich::new(Line::Row(4), 12).unwrap()),
+            Arc::new(Sandwich::new(Line::Col(8), 0).unwrap()),
+        ];
+        for c in constraints.iter() {
+            b.push_constraint(c.clone());
+        }
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+        // all constraints are satisfied by the answer
+        for c in constraints.iter() {
+            assert_eq!(Ok(false), c.propagate(&mut b.g.clone()));
+        }
+
+        // 1 and 9 are at both ends of the row, but both ends are bulbs
+        let mut b = Ripple::new(&".".repeat(81)).unwrap();
+        b.add_constraint(Sandwich::new(Line::Row(0), 35).unwrap());
+        b.add_constraint(Thermo::new(&cells(&[(0, 0), (0, 1)])).unwrap());
+        b.add_constraint(Thermo::new(&cells(&[(0, 8), (0, 7)])).unwrap());
+        assert_eq!(false, b.do_solve());
+    }
+
+    #[test]
+    fn comparison() {
+        let solved = "
+		123456789
+		457389162
+		869271453
+		372594618
+		581762394
+		694813527
+		715948236
+		248635971
+		936127845
+		";
+        let ans = Board::new(solved).unwrap();
+        // all signs inside blocks and parity of the first row
+        let mut signs = String::new();
+        for i in 0..9 {
+            for j in 0..9 {
+                for &(r, c) in [(i, j + 1), (i + 1, j)].iter() {
+                    if r % 3 == 0 && r != i || c % 3 == 0 && c != j {
+                        continue;
+                    }
+                    let sign = if ans.lucky(Cell::new(i, j)) > ans.lucky(Cell::new(r, c)) {
+                        '>'
+                    } else {
+                        '<'
+                    };
+                    signs.push_str(&format!(
+                        "r{}c{} {} r{}c{}\n",
+                        i + 1,
+                        j + 1,
+                        sign,
+                        r + 1,
+                        c + 1
+                    ));
+                }
+            }
+        }
+        let mut g = Board::new(&".".repeat(81)).unwrap();
+        assert_eq!(true, g.mask_parity(&format!("oeoeoeoeo{}", ".".repeat(72))));
+        let mut b = Ripple::from_board(g);
+        let pairs = Pairwise::inequalities(&signs).unwrap();
+        assert_eq!(108, pairs.iter().count());
+        b.add_constraint(pairs.clone());
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+        assert_eq!(Ok(false), pairs.propagate(&mut b.g.clone()));
+        assert_eq!('1', b.g.lucky(Cell::new(0, 0)));
+    }
+
+    // a house rule out of the crate: the two cells sum to 10
+    #[derive(Debug)]
+    struct Xv(Cell, Cell);
+    impl Constraint for Xv {
+        fn cells(&self) -> Vec<Cell> {
+            vec![self.0, self.1]
+        }
+        fn propagate(&self, g: &mut Board) -> Result<Changed, Contradiction> {
+            let mut changed = false;
+            for &(x, y) in [(self.0, self.1), (self.1, self.0)].iter() {
+                // 10 - y, by reversing bits of 1-9
+                let bits = g.cell(y).bits().reverse_bits() >> 7;
+                changed |= restrict(g, x, Candidates::from_bits(bits))?;
+            }
+            Ok(changed)
+        }
+    }
+
+    #[test]
+    fn custom() {
+        let mut b = Ripple::new(&".".repeat(81)).unwrap();
+        b.add_constraint(Xv(Cell::new(0, 0), Cell::new(0, 1)));
+        b.add_constraint(Xv(Cell::new(0, 1), Cell::new(1, 3)));
+        b.add_constraint(Xv(Cell::new(4, 4), Cell::new(4, 5)));
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+        assert_eq!(
+            10,
+            b.g.lucky(Cell::new(0, 0)) as u8 + b.g.lucky(Cell::new(0, 1)) as u8 - b'0' * 2
+        );
+        assert_eq!(b.g.lucky(Cell::new(0, 0)), b.g.lucky(Cell::new(1, 3)));
+        assert_ne!('5', b.g.lucky(Cell::new(4, 4)));
+
+        let mut b = Ripple::new(&".".repeat(81)).unwrap();
+        b.add_constraint(Xv(Cell::new(0, 0), Cell::new(0, 1)));
+        b.add_constraint(Xv(Cell::new(0, 1), Cell::new(0, 2)));
+        assert_eq!(false, b.do_solve());
+        assert_eq!(b.clone(), b);
+    }
+
+    #[test]
+    fn resume_pencil_marks() {
+        // `hard` after the initial ripple, as in README
+        let s = "
+  +---------------------------------------------------------------+
+  |   8    1246  24569 | 2347  12357  1234  | 13569 4579  1345679 |
+  | 12459   124    3   |   6   12578  1248  | 1589  45789  14579  |
+  |  1456    7    456  |  348    9    1348  |   2    458   13456  |
+  +---------------------------------------------------------------+
+  | 123469   5   2469  | 2389   2368    7   | 1689  2489   12469  |
+  | 12369  12368  269  | 2389    4      5   |   7    289   1269   |
+  | 24679  2468  24679 |   1    268   2689  | 5689    3    24569  |
+  +---------------------------------------------------------------+
+  | 23457   234    1   | 23479  237   2349  |  359    6      8    |
+  | 23467  2346    8   |   5    2367  23469 |  39     1    2379   |
+  | 23567    9   2567  | 2378  123678 12368 |   4    257   2357   |
+  +---------------------------------------------------------------+
+  ";
+        let mut b = Ripple::from_board(Board::from_pencil_marks(s).unwrap());
+        assert_eq!(true, b.do_solve());
+        assert_valid(&b);
+        let hard =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        assert_eq!(
+            Ripple::solve(hard),
+            Some(b.g.iter().flatten().map(|c| c.lucky()).collect())
+        );
+    }
+
+    #[test]
+    fn answer() {
+        let hard =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        let mut b = Ripple::new(hard).unwrap();
+        assert_eq!(None, b.answer().solution);
+        assert_eq!(Stats::default(), b.stats());
+        assert_eq!(true, b.do_solve());
+        let ans = b.answer();
+        assert_eq!(Ripple::solve(hard), ans.solution);
+        assert_eq!(true, ans.stats.tries > 0);
+        assert_eq!(b.stats(), ans.stats);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut b = Ripple::new(&".".repeat(81)).unwrap();
+        assert_eq!(true, b.do_solve());
+        let ans = b.answer();
+        let json = serde_json::to_value(&ans).unwrap();
+        assert_eq!(81, json["solution"].as_str().unwrap().len());
+        assert_eq!(
+            ans.stats.tries,
+            json["stats"]["tries"].as_u64().unwrap() as usize
+        );
+        assert_eq!(ans, serde_json::from_value(json).unwrap());
+    }
+
+    #[test]
+    fn stats_sum() {
+        let mut total = Stats::default();
+        total += Stats {
+            tries: 3,
+            triplexes: 1,
+        };
+        total += Stats {
+            tries: 2,
+            triplexes: 0,
+        };
+        assert_eq!(
+            Stats {
+                tries: 5,
+                triplexes: 1
+            },
+            total
+        );
+    }
+
+    #[test]
+    fn batch() {
+        let hard = Board::new(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..",
+        )
+        .unwrap();
+        let mut broken = hard;
+        broken.set_cell(Cell::new(0, 1), Candidates::from('8'));
+        let puzzles = (0..130)
+            .map(|k| if k % 7 == 3 { broken } else { hard })
+            .collect::<Vec<_>>();
+        let answers = solve_batch(&puzzles, 4);
+        assert_eq!(130, answers.len());
+        assert_eq!(answers, solve_batch(&puzzles, 1));
+        for (k, answer) in answers.iter().enumerate() {
+            assert_eq!(k % 7 != 3, answer.solution.is_some());
+        }
+        assert_eq!(true, solve_batch(&[], 0).is_empty());
+
+        let mut many = hard;
+        many.set_cell(Cell::new(0, 0), Candidates::new());
+        let reports = count_batch(&[hard, broken, many], 2, 2);
+        assert_eq!(
+            vec![1, 0, 2],
+            reports.iter().map(|r| r.solutions).collect::<Vec<_>>()
+        );
+        assert_eq!(answers[0].solution, reports[0].answer.solution);
+        assert_eq!(None, reports[1].answer.solution);
+        assert_eq!(true, reports[2].answer.solution.is_some());
+    }
+
+    #[test]
+    fn count() {
+        let hard =
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        let mut b = Ripple::new(hard).unwrap();
+        assert_eq!(1, b.count(10));
+        assert_eq!(Ripple::solve(hard), b.answer().solution);
+
+        // r1c1 is free, other numbers of the answer are kept
+        let ans = Ripple::solve(hard).unwrap();
+        let mut b = Ripple::new(&format!(".{}", &ans[1..])).unwrap();
+        assert_eq!(1, b.count(10));
+        // four cells of a deadly pattern, 3 and 9 can swap
+        let s = "12.45678.45738916286.27145.372594618581762394694813527715948236248635971936127845";
+        assert_eq!(2, Ripple::new(s).unwrap().count(10));
+        assert_eq!(1, Ripple::new(s).unwrap().count(1));
+        assert_eq!(10, Ripple::new(&".".repeat(81)).unwrap().count(10));
+        assert_eq!(
+            0,
+            Ripple::new(&hard.replacen('.', "8", 1)).unwrap().count(10)
+        );
+        // a cell of no candidate, e.g. read from a pencil-mark grid
+        let mut g = Board::new(&".".repeat(81)).unwrap();
+        g.set_cell(Cell::new(4, 4), Candidates::from_bits(0));
+        assert_eq!(0, Ripple::from_board(g).count(10));
+        assert_eq!(false, Ripple::from_board(g).do_solve());
+    }
+
     #[test]
     fn hard_no_ans() {
         let s = "
@@ -0,0 +1,341 @@
+//! JSON-RPC 2.0 of the library over stdin/stdout, a request or a batch of
+//! them per line, and a response per line
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Map, Value};
+
+use sudoku::board::Board;
+use sudoku::cell::Cell;
+use sudoku::formats::Format;
+use sudoku::generate::Generator;
+use sudoku::hint::{self, Difficulty, Hint};
+use sudoku::ripple::*;
+use sudoku::variant::Variant;
+
+use crate::{MULTIPLE, NOT_FOUND, UNSOLVABLE};
+
+// errors of JSON-RPC, others are exit codes of the binary
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+
+// counting is stopped at the limit, a bigger one takes too long to answer
+const MAX_LIMIT: u64 = 10_000;
+
+type Error = (i32, String);
+
+/// serve requests until stdin is closed
+pub fn run() -> io::Result<()> {
+    let mut stdin = io::stdin().lock();
+    let mut out = io::stdout().lock();
+    // bytes, as a line which isn't UTF-8 gets a parse error
+    let mut line = Vec::new();
+    while stdin.read_until(b'\n', &mut line)? > 0 {
+        if let Some(response) = respond(&line) {
+            writeln!(out, "{}", response)?;
+            out.flush()?;
+        }
+        line.clear();
+    }
+    Ok(())
+}
+
+// the response of a line, `None` if it's blank or of notifications only
+fn respond(line: &[u8]) -> Option<Value> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return None;
+    }
+    match serde_json::from_slice::<Value>(line) {
+        Ok(Value::Array(requests)) if !requests.is_empty() => {
+            let responses = requests.into_iter().filter_map(handle).collect::<Vec<_>>();
+            if responses.is_empty() {
+                None
+            } else {
+                Some(Value::Array(responses))
+            }
+        }
+        Ok(Value::Array(_)) => Some(error(
+            Value::Null,
+            (INVALID_REQUEST, "empty batch".to_string()),
+        )),
+        Ok(request) => handle(request),
+        Err(e) => Some(error(Value::Null, (PARSE_ERROR, e.to_string()))),
+    }
+}
+
+// the response of a request, `None` for notifications
+fn handle(request: Value) -> Option<Value> {
+    let id = request.get("id").cloned();
+    let method = match (request.get("jsonrpc"), request.get("method")) {
+        (Some(Value::String(v)), Some(Value::String(method))) if v == "2.0" => method.clone(),
+        _ => {
+            return Some(error(
+                id.unwrap_or(Value::Null),
+                (INVALID_REQUEST, "not a JSON-RPC 2.0 request".to_string()),
+            ))
+        }
+    };
+    let params = match request.get("params") {
+        None | Some(Value::Null) => Map::new(),
+        Some(Value::Object(params)) => params.clone(),
+        Some(_) => {
+            return id
+                .map(|id| error(id, (INVALID_PARAMS, "params must be an object".to_string())));
+        }
+    };
+    let result = call(&method, &params);
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(e) => error(id, e),
+    })
+}
+fn error(id: Value, (code, message): Error) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+fn call(method: &str, params: &Map<String, Value>) -> Result<Value, Error> {
+    match method {
+        "solve" => {
+            let report = count_batch(&[puzzle(params)?], 2, 1).remove(0);
+            let status = match report.solutions {
+                0 => "unsolvable",
+                1 => "solved",
+                _ => "multiple",
+            };
+            Ok(json!({
+                "status": status,
+                "solution": report.answer.solution,
+                "solutions": report.solutions,
+                "stats": report.answer.stats,
+                "time_us": report.time.as_micros() as u64,
+            }))
+        }
+        "count" => {
+            let limit = match params.get("limit") {
+                None => 1000,
+                Some(limit) => match limit.as_u64() {
+                    Some(limit) if limit <= MAX_LIMIT => limit as usize,
+                    _ => {
+                        return Err(invalid(&format!(
+                            "limit must be a number up to {}",
+                            MAX_LIMIT
+                        )))
+                    }
+                },
+            };
+            Ok(json!({"count": Ripple::from_board(puzzle(params)?).count(limit)}))
+        }
+        "hint" => {
+            let g = puzzle(params)?;
+            if Ripple::from_board(g).count(1) == 0 {
+                return Err((UNSOLVABLE, "no answer".to_string()));
+            }
+            Ok(hint::next(&g).map_or(Value::Null, |hint| hint_json(&hint)))
+        }
+        "rate" => {
+            let g = puzzle(params)?;
+            match Ripple::from_board(g).count(2) {
+                0 => Err((UNSOLVABLE, "no answer".to_string())),
+                1 => {
+                    let rating = hint::rate(&g);
+                    Ok(json!({"difficulty": rating.difficulty.to_string(), "steps": rating.steps}))
+                }
+                _ => Err((MULTIPLE, "multiple answers".to_string())),
+            }
+        }
+        "generate" => {
+            let seed = match params.get("seed") {
+                None => std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos() as u64),
+                Some(seed) => seed
+                    .as_u64()
+                    .ok_or_else(|| invalid("seed must be a number"))?,
+            };
+            let mut generator = Generator::new(seed);
+            let puzzle = match params.get("difficulty") {
+                None => generator.puzzle(),
+                Some(d) => {
+                    let d = d
+                        .as_str()
+                        .and_then(Difficulty::from_name)
+                        .ok_or_else(|| invalid("unknown difficulty"))?;
+                    generator
+                        .puzzle_of(d, 100)
+                        .ok_or_else(|| (NOT_FOUND, "no puzzle found".to_string()))?
+                }
+            };
+            let line = Format::Line.write(&[puzzle]);
+            Ok(json!({"puzzle": line.trim_end(), "seed": seed}))
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+    }
+}
+
+// `puzzle` as a string in any format of the command line, or a board object,
+// with rules of `variant` added
+fn puzzle(params: &Map<String, Value>) -> Result<Board, Error> {
+    let mut g = match params.get("puzzle") {
+        Some(Value::String(s)) => crate::parse(s).ok_or_else(|| invalid("invalid puzzle"))?,
+        Some(b @ Value::Object(_)) => {
+            serde_json::from_value::<Board>(b.clone()).map_err(|e| invalid(&e.to_string()))?
+        }
+        _ => return Err(invalid("puzzle is missing")),
+    };
+    if let Some(names) = params.get("variant") {
+        let rules = names
+            .as_array()
+            .ok_or_else(|| invalid("variant must be an array"))?
+            .iter()
+            .map(|name| name.as_str().and_then(Variant::from_name))
+            .try_fold(g.variant(), |v, rule| Some(v | rule?))
+            .ok_or_else(|| invalid("unknown variant"))?;
+        g.set_variant(rules);
+    }
+    Ok(g)
+}
+fn invalid(message: &str) -> Error {
+    (INVALID_PARAMS, message.to_string())
+}
+
+// e.g. {"technique": "hidden single", "message": "hidden single: r5c3 is 7, ...",
+// "unit": "box 4", "cells": ["r5c3"], "numbers": [7],
+// "place": {"cell": "r5c3", "number": 7}, "eliminations": []}
+fn hint_json(hint: &Hint) -> Value {
+    let cell = |&(c, n): &(Cell, u8)| json!({"cell": c.to_string(), "number": n});
+    json!({
+        "technique": hint.technique.to_string(),
+        "message": hint.to_string(),
+        "unit": hint.unit.map(|u| u.to_string()),
+        "cells": hint.cells.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+        "numbers": hint.numbers,
+        "place": hint.place.as_ref().map(cell),
+        "eliminations": hint.eliminations.iter().map(cell).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{ANSWER, HARD};
+
+    fn respond(line: &str) -> Option<Value> {
+        super::respond(line.as_bytes())
+    }
+    fn request(method: &str, params: Value) -> String {
+        json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}).to_string()
+    }
+    fn code(response: &Value) -> i64 {
+        response["error"]["code"].as_i64().unwrap()
+    }
+
+    #[test]
+    fn protocol() {
+        assert_eq!(None, respond("  "));
+        let r = respond("{").unwrap();
+        assert_eq!(PARSE_ERROR as i64, code(&r));
+        assert_eq!(Value::Null, r["id"]);
+        let r = super::respond(b"{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"\xff\"}\n");
+        assert_eq!(PARSE_ERROR as i64, code(&r.unwrap()));
+        assert_eq!(None, super::respond(b" \r\n"));
+        assert_eq!(INVALID_REQUEST as i64, code(&respond("[]").unwrap()));
+        assert_eq!(
+            INVALID_REQUEST as i64,
+            code(&respond(r#"{"id": 1, "method": "solve"}"#).unwrap())
+        );
+        assert_eq!(
+            INVALID_REQUEST as i64,
+            code(&respond(r#"{"jsonrpc": "2.0", "id": 1}"#).unwrap())
+        );
+        let r = respond(r#"{"jsonrpc": "2.0", "id": "a", "method": "x"}"#).unwrap();
+        assert_eq!(METHOD_NOT_FOUND as i64, code(&r));
+        assert_eq!("a", r["id"]);
+        let r =
+            respond(r#"{"jsonrpc": "2.0", "id": 2, "method": "solve", "params": [1]}"#).unwrap();
+        assert_eq!(INVALID_PARAMS as i64, code(&r));
+
+        // notifications get no response, even of errors
+        let notification = json!({"jsonrpc": "2.0", "method": "count", "params": {"puzzle": HARD}});
+        assert_eq!(None, respond(&notification.to_string()));
+        assert_eq!(None, respond(r#"{"jsonrpc": "2.0", "method": "x"}"#));
+        assert_eq!(None, respond(&format!("[{}]", notification)));
+        // a response per request of a batch, in order
+        let batch = format!("[{}, {}, 5]", request("x", json!({})), notification);
+        let r = respond(&batch).unwrap();
+        assert_eq!(2, r.as_array().unwrap().len());
+        assert_eq!(METHOD_NOT_FOUND as i64, code(&r[0]));
+        assert_eq!(INVALID_REQUEST as i64, code(&r[1]));
+    }
+
+    #[test]
+    fn methods() {
+        let r = respond(&request("solve", json!({"puzzle": HARD}))).unwrap();
+        assert_eq!("2.0", r["jsonrpc"]);
+        assert_eq!(1, r["id"]);
+        assert_eq!("solved", r["result"]["status"]);
+        assert_eq!(ANSWER, r["result"]["solution"]);
+        assert_eq!(1, r["result"]["solutions"]);
+        assert!(r["result"]["stats"]["tries"].is_u64());
+        assert!(r["result"]["time_us"].is_u64());
+
+        let r = respond(&request(
+            "count",
+            json!({"puzzle": ".".repeat(81), "limit": 3}),
+        ))
+        .unwrap();
+        assert_eq!(json!({"count": 3}), r["result"]);
+        let r = respond(&request(
+            "count",
+            json!({"puzzle": HARD, "limit": MAX_LIMIT + 1}),
+        ))
+        .unwrap();
+        assert_eq!(INVALID_PARAMS as i64, code(&r));
+        let r = respond(&request("count", json!({"puzzle": HARD, "limit": -1}))).unwrap();
+        assert_eq!(INVALID_PARAMS as i64, code(&r));
+        let r = respond(&request("count", json!({"puzzle": "1"}))).unwrap();
+        assert_eq!(INVALID_PARAMS as i64, code(&r));
+        // a board object with variant rules
+        let board = json!({"givens": ".".repeat(81), "variant": ["anti-king"]});
+        let r = respond(&request(
+            "count",
+            json!({"puzzle": board, "variant": ["disjoint"], "limit": 1}),
+        ));
+        assert_eq!(json!({"count": 1}), r.unwrap()["result"]);
+        let r = respond(&request("count", json!({"puzzle": HARD, "variant": ["x"]}))).unwrap();
+        assert_eq!(INVALID_PARAMS as i64, code(&r));
+
+        // the answer with r1c1 blank
+        let easy = format!(".{}", &ANSWER[1..]);
+        let r = respond(&request("hint", json!({"puzzle": easy}))).unwrap();
+        assert_eq!(json!({"cell": "r1c1", "number": 8}), r["result"]["place"]);
+        assert!(r["result"]["technique"].is_string());
+        assert!(r["result"]["message"].is_string());
+        assert!(r["result"]["eliminations"].is_array());
+        // none found by the techniques
+        let r = respond(&request("hint", json!({"puzzle": HARD}))).unwrap();
+        assert_eq!(Value::Null, r["result"]);
+        let unsolvable = HARD.replacen('.', "8", 1);
+        let r = respond(&request("hint", json!({"puzzle": unsolvable}))).unwrap();
+        assert_eq!(UNSOLVABLE as i64, code(&r));
+
+        let r = respond(&request("rate", json!({"puzzle": HARD}))).unwrap();
+        assert!(r["result"]["difficulty"].is_string());
+        assert!(r["result"]["steps"].is_u64());
+        let r = respond(&request("rate", json!({"puzzle": ".".repeat(81)}))).unwrap();
+        assert_eq!(MULTIPLE as i64, code(&r));
+
+        let r = respond(&request("generate", json!({"seed": 7}))).unwrap();
+        assert_eq!(7, r["result"]["seed"]);
+        let puzzle = r["result"]["puzzle"].as_str().unwrap();
+        assert_eq!(1, Ripple::new(puzzle).unwrap().count(2));
+        assert_eq!(
+            r,
+            respond(&request("generate", json!({"seed": 7}))).unwrap()
+        );
+        let r = respond(&request("generate", json!({"difficulty": "x"}))).unwrap();
+        assert_eq!(INVALID_PARAMS as i64, code(&r));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::board::Board;
+use crate::candidates::Candidates;
+use crate::cell::Cell;
+use crate::constraint::{Arrow, Line, Sandwich, Thermo};
+use crate::pairwise::{Pairwise, Relation};
+use crate::ripple::Ripple;
+use crate::variant::Variant;
+
+// version of the share string, the first byte
+const VERSION: u8 = 1;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// tags of constraints, 3 bits
+const END: u16 = 0;
+const PAIR: u16 = 1;
+const THERMO: u16 = 2;
+const ARROW: u16 = 3;
+const SANDWICH: u16 = 4;
+const RELATIONS: [Relation; 6] = [
+    Relation::Consecutive,
+    Relation::Double,
+    Relation::NonConsecutive,
+    Relation::Neither,
+    Relation::Greater,
+    Relation::Less,
+];
+
+/// Constraints of a share string beyond variant rules, e.g. kropki dots
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Constraints {
+    pub pairs: Pairwise,
+    pub thermos: Vec<Thermo>,
+    pub arrows: Vec<Arrow>,
+    pub sandwiches: Vec<Sandwich>,
+}
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+            && self.thermos.is_empty()
+            && self.arrows.is_empty()
+            && self.sandwiches.is_empty()
+    }
+    /// add all of them to the solver
+    pub fn add_to(&self, r: &mut Ripple) {
+        if !self.pairs.is_empty() {
+            r.add_constraint(self.pairs.clone());
+        }
+        for t in self.thermos.iter() {
+            r.add_constraint(t.clone());
+        }
+        for a in self.arrows.iter() {
+            r.add_constraint(a.clone());
+        }
+        for &s in self.sandwiches.iter() {
+            r.add_constraint(s);
+        }
+    }
+}
+
+/// URL-safe share string of the board, with variant rules and pencil marks
+///
+/// Bytes are the version, the variant, then candidates of each cell in
+/// reading order as a prefix code: `0` for all of 1-9, `10` and 4 bits for a
+/// solved cell, `11` and 9 bits for others, then constraints of
+/// [`encode_with`] ended by 3 bits of 0. They are written as base64url
+/// without padding, e.g. about 36 chars for a puzzle of 25 givens.
+pub fn encode(b: &Board) -> String {
+    encode_with(b, &Constraints::default())
+}
+/// share string of the board with constraints, each of them is a 3-bit tag
+/// and its cells as 7-bit indexes:
+/// - `1`: kropki dot or inequality sign, two cells and 3 bits of the relation
+/// - `2`: thermometer, 4 bits of the length and cells from the bulb
+/// - `3`: arrow, the circle, 4 bits of the length and cells of the path
+/// - `4`: sandwich, 1 bit for row or col, 4 bits of the line and 6 of the sum
+pub fn encode_with(b: &Board, constraints: &Constraints) -> String {
+    let mut w = BitWriter::default();
+    w.push(VERSION as u16, 8);
+    w.push(b.variant().index() as u16, 8);
+    for c in Cell::all() {
+        let cs = b.cell(c);
+        if cs.bits() == 0x1FF {
+            w.push(0, 1);
+        } else if cs.len() == 1 {
+            w.push(0b10, 2);
+            w.push(cs.bits().trailing_zeros() as u16, 4);
+        } else {
+            w.push(0b11, 2);
+            w.push(cs.bits(), 9);
+        }
+    }
+    for pair in constraints.pairs.iter() {
+        w.push(PAIR, 3);
+        w.cells(&[pair.a, pair.b]);
+        let rel = RELATIONS.iter().position(|&rel| rel == pair.rel).unwrap();
+        w.push(rel as u16, 3);
+    }
+    for t in constraints.thermos.iter() {
+        w.push(THERMO, 3);
+        w.push(t.path().len() as u16, 4);
+        w.cells(t.path());
+    }
+    for a in constraints.arrows.iter() {
+        w.push(ARROW, 3);
+        w.cells(&[a.circle()]);
+        w.push(a.path().len() as u16, 4);
+        w.cells(a.path());
+    }
+    for s in constraints.sandwiches.iter() {
+        w.push(SANDWICH, 3);
+        match s.line() {
+            Line::Row(k) => w.push(k as u16, 5),
+            Line::Col(k) => w.push(0b10000 | k as u16, 5),
+        }
+        w.push(s.sum() as u16, 6);
+    }
+    w.push(END, 3);
+
+    w.bytes
+        .chunks(3)
+        .flat_map(|chunk| {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |n, (k, &byte)| n | (byte as u32) << (16 - k * 8));
+            (0..=chunk.len()).map(move |k| ALPHABET[(n >> (18 - k * 6)) as usize & 0x3F] as char)
+        })
+        .collect()
+}
+/// board of the share string, `None` if it's invalid, of another version, or
+/// has constraints
+pub fn decode(s: &str) -> Option<Board> {
+    match decode_with(s)? {
+        (b, constraints) if constraints.is_empty() => Some(b),
+        _ => None,
+    }
+}
+/// board and constraints of the share string, `None` if it's invalid or of
+/// another version
+pub fn decode_with(s: &str) -> Option<(Board, Constraints)> {
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut n, mut bits) = (0u32, 0);
+    for c in s.bytes() {
+        let v = ALPHABET.iter().position(|&a| a == c)? as u32;
+        n = (n << 6) | v;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((n >> bits) as u8);
+            n &= (1 << bits) - 1;
+        }
+    }
+
+    let mut r = BitReader {
+        bytes: &bytes,
+        pos: 0,
+    };
+    if r.pop(8)? != VERSION as u16 {
+        return None;
+    }
+    let mut b = Board::default();
+    b.set_variant(Variant::from_index(r.pop(8)? as usize)?);
+    for c in Cell::all() {
+        let cs = match r.pop(1)? {
+            0 => Candidates::new(),
+            _ if r.pop(1)? == 0 => match r.pop(4)? {
+                n @ 0..=8 => Candidates::from_bits(1 << n),
+                _ => return None,
+            },
+            _ => Candidates::from_bits(r.pop(9)?),
+        };
+        b.set_cell(c, cs);
+    }
+    let mut constraints = Constraints::default();
+    loop {
+        match r.pop(3)? {
+            END => break,
+            PAIR => {
+                let (a, c) = (r.cell()?, r.cell()?);
+                let rel = *RELATIONS.get(r.pop(3)? as usize)?;
+                constraints.pairs.add(a, c, rel);
+            }
+            THERMO => {
+                let path = r.cells()?;
+                constraints.thermos.push(Thermo::new(&path)?);
+            }
+            ARROW => {
+                let circle = r.cell()?;
+                let path = r.cells()?;
+                constraints.arrows.push(Arrow::new(circle, &path)?);
+            }
+            SANDWICH => {
+                let line = match r.pop(5)? as usize {
+                    k @ 0..=8 => Line::Row(k),
+                    k => Line::Col(k & 0b1111),
+                };
+                constraints
+                    .sandwiches
+                    .push(Sandwich::new(line, r.pop(6)? as u8)?);
+            }
+            _ => return None,
+        }
+    }
+    // one string for each board, e.g. no trailing chars or bits
+    if encode_with(&b, &constraints) != s {
+        return None;
+    }
+    Some((b, constraints))
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+impl BitWriter {
+    // the lowest `n` bits of `v`, high bits first
+    fn push(&mut self, v: u16, n: usize) {
+        for k in (0..n).rev() {
+            if self.len % 8 == 0 {
+                self.bytes.push(0);
+            }
+            if v & (1 << k) != 0 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+    fn cells(&mut self, cells: &[Cell]) {
+        for c in cells {
+            self.push(c.index() as u16, 7);
+        }
+    }
+}
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl BitReader<'_> {
+    fn pop(&mut self, n: usize) -> Option<u16> {
+        let mut v = 0;
+        for _ in 0..n {
+            let byte = self.bytes.get(self.pos / 8)?;
+            v = (v << 1) | ((byte >> (7 - self.pos % 8)) & 1) as u16;
+            self.pos += 1;
+        }
+        Some(v)
+    }
+    fn cell(&mut self) -> Option<Cell> {
+        match self.pop(7)? as usize {
+            k @ 0..=80 => Some(Cell::from_index(k)),
+            _ => None,
+        }
+    }
+    // 4 bits of the length and the cells
+    fn cells(&mut self) -> Option<Vec<Cell>> {
+        let n = self.pop(4)?;
+        (0..n).map(|_| self.cell()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HARD;
+
+    #[test]
+    fn round_trip() {
+        let b = Board::new(HARD).unwrap();
+        let s = encode(&b);
+        assert!(s.len() <= 36);
+        assert!(s
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+        assert_eq!(Some(b), decode(&s));
+
+        // pencil marks, an invalid cell and variant rules
+        let mut g = b;
+        g.set_cell(Cell::new(0, 1), Candidates::from_bits(0b101011));
+        g.set_cell(Cell::new(8, 8), Candidates::from_bits(0));
+        g.set_variant(Variant::ANTI_KNIGHT | Variant::DISJOINT);
+        let s = encode(&g);
+        assert_eq!(Some(g), decode(&s));
+        assert_ne!(Some(b), decode(&s));
+
+        let empty = Board::new(&".".repeat(81)).unwrap();
+        assert_eq!(Some(empty), decode(&encode(&empty)));
+    }
+
+    #[test]
+    fn invalid() {
+        let s = encode(&Board::new(HARD).unwrap());
+        assert_eq!(None, decode(""));
+        assert_eq!(None, decode(&s[..s.len() - 2]));
+        assert_eq!(None, decode(&format!("{}AA", s)));
+        assert_eq!(None, decode(&s.replacen('A', "+", 1)));
+        // version 2
+        assert_eq!(None, decode(&format!("Ag{}", &s[2..])));
+        // non-canonical, a trailing char of zero bits or padding bits set
+        assert_eq!(None, decode(&format!("{}A", s)));
+        let last = ALPHABET
+            .iter()
+            .position(|&a| a == s.as_bytes()[s.len() - 1]);
+        let set = ALPHABET[last.unwrap() | 1] as char;
+        assert_eq!(None, decode(&format!("{}{}", &s[..s.len() - 1], set)));
+    }
+
+    #[test]
+    fn constraints() {
+        let b = Board::new(&".".repeat(81)).unwrap();
+        let mut pairs = Pairwise::kropki("r1c1 r1c2 w\nr1c1 r2c1 b", false).unwrap();
+        pairs.append(Pairwise::inequalities("r9c8 > r9c9").unwrap());
+        let path = [Cell::new(4, 4), Cell::new(4, 5), Cell::new(5, 6)];
+        let arrow = Arrow::new(Cell::new(8, 0), &[Cell::new(7, 0), Cell::new(6, 0)]);
+        let constraints = Constraints {
+            pairs,
+            thermos: vec![Thermo::new(&path).unwrap()],
+            arrows: vec![arrow.unwrap()],
+            sandwiches: vec![
+                Sandwich::new(Line::Col(8), 35).unwrap(),
+                Sandwich::new(Line::Row(2), 0).unwrap(),
+            ],
+        };
+
+        let s = encode_with(&b, &constraints);
+        assert_eq!(Some((b, constraints.clone())), decode_with(&s));
+        // a board alone can't keep them
+        assert_eq!(None, decode(&s));
+        assert_eq!(Some((b, Constraints::default())), decode_with(&encode(&b)));
+
+        let mut r = Ripple::from_board(b);
+        constraints.add_to(&mut r);
+        assert!(r.do_solve());
+        let n = |c: Cell| r.board().cell(c).bits();
+        assert!(n(path[0]) < n(path[1]) && n(path[1]) < n(path[2]));
+        // 1 and 9 at both ends of col 9
+        assert_eq!(0x101, n(Cell::new(0, 8)) | n(Cell::new(8, 8)));
+    }
+}
@@ -0,0 +1,8 @@
+//! fixtures shared by the tests of the library and the binary
+
+/// `hard` of README, 21 givens and one answer
+pub const HARD: &str =
+    "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+/// the answer of `HARD`
+pub const ANSWER: &str =
+    "812753649943682175675491283154237896369845721287169534521974368438526917796318452";
@@ -0,0 +1,166 @@
+use std::ops::BitOr;
+
+use crate::cell::Cell;
+
+/// Global variant rules of a board
+///
+/// Each rule adds more peers to cells, e.g. with `ANTI_KNIGHT` two cells a
+/// chess knight's move apart can't share a digit, so they are peers.
+/// Rules can be combined with `|`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Variant(u8);
+impl Variant {
+    pub const CLASSIC: Self = Self(0);
+    pub const ANTI_KNIGHT: Self = Self(1);
+    pub const ANTI_KING: Self = Self(1 << 1);
+    /// cells in the same position of each block form an extra unit
+    pub const DISJOINT: Self = Self(1 << 2);
+    /// centre cells of all blocks form an extra unit
+    pub const CENTER_DOT: Self = Self(1 << 3);
+
+    /// names of rules, e.g. for command line
+    pub const NAMES: [(&'static str, Self); 4] = [
+        ("anti-knight", Self::ANTI_KNIGHT),
+        ("anti-king", Self::ANTI_KING),
+        ("disjoint", Self::DISJOINT),
+        ("center-dot", Self::CENTER_DOT),
+    ];
+
+    // number of all combinations of rules
+    pub(crate) const COUNT: usize = 1 << 4;
+
+    /// names of all rules in the variant
+    pub fn names(self) -> Vec<&'static str> {
+        Self::NAMES
+            .iter()
+            .filter(|&&(_, v)| self.contains(v))
+            .map(|&(name, _)| name)
+            .collect()
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, v)| v)
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub(crate) fn index(self) -> usize {
+        self.0 as usize
+    }
+    pub(crate) fn from_index(k: usize) -> Option<Self> {
+        if k < Self::COUNT {
+            Some(Self(k as u8))
+        } else {
+            None
+        }
+    }
+
+    /// whether two different cells can't share a digit under the rules
+    pub fn sees(self, a: Cell, b: Cell) -> bool {
+        if a == b {
+            return false;
+        }
+        let (r1, c1, r2, c2) = (a.row(), a.col(), b.row(), b.col());
+        // same row, same col or same block
+        if r1 == r2 || c1 == c2 || (r1 / 3 == r2 / 3 && c1 / 3 == c2 / 3) {
+            return true;
+        }
+
+        let (dr, dc) = (r1.max(r2) - r1.min(r2), c1.max(c2) - c1.min(c2));
+        if self.contains(Self::ANTI_KNIGHT) && ((dr, dc) == (1, 2) || (dr, dc) == (2, 1)) {
+            return true;
+        }
+        if self.contains(Self::ANTI_KING) && dr <= 1 && dc <= 1 {
+            return true;
+        }
+        if self.contains(Self::DISJOINT) && (r1 % 3, c1 % 3) == (r2 % 3, c2 % 3) {
+            return true;
+        }
+        if self.contains(Self::CENTER_DOT) && (r1 % 3, c1 % 3, r2 % 3, c2 % 3) == (1, 1, 1, 1) {
+            return true;
+        }
+        false
+    }
+}
+impl BitOr for Variant {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_peers(v: Variant, (row, col): (usize, usize)) -> usize {
+        Cell::all()
+            .filter(|&c| v.sees(Cell::new(row, col), c))
+            .count()
+    }
+
+    #[test]
+    fn classic() {
+        for i in 0..81 {
+            assert_eq!(20, count_peers(Variant::CLASSIC, (i / 9, i % 9)));
+        }
+        assert!(!Variant::CLASSIC.sees(Cell::new(4, 4), Cell::new(4, 4)));
+        assert!(!Variant::CLASSIC.sees(Cell::new(4, 4), Cell::new(6, 5)));
+    }
+
+    #[test]
+    fn anti_knight() {
+        let v = Variant::ANTI_KNIGHT;
+        assert!(v.sees(Cell::new(4, 4), Cell::new(6, 5)));
+        assert!(v.sees(Cell::new(4, 4), Cell::new(3, 6)));
+        // knight moves inside the block are already peers
+        assert_eq!(28, count_peers(v, (4, 4)));
+        assert_eq!(20, count_peers(v, (0, 0)));
+        assert_eq!(22, count_peers(v, (0, 2)));
+    }
+
+    #[test]
+    fn anti_king() {
+        let v = Variant::ANTI_KING;
+        assert!(v.sees(Cell::new(2, 2), Cell::new(3, 3)));
+        assert!(!v.sees(Cell::new(2, 2), Cell::new(4, 4)));
+        // diagonal neighbours inside the block are already peers
+        assert_eq!(20, count_peers(v, (4, 4)));
+        assert_eq!(23, count_peers(v, (3, 3)));
+
+        let v = Variant::ANTI_KNIGHT | Variant::ANTI_KING;
+        assert!(v.contains(Variant::ANTI_KING));
+        assert_eq!(29, count_peers(v, (3, 3)));
+    }
+
+    #[test]
+    fn disjoint() {
+        let v = Variant::DISJOINT;
+        assert!(v.sees(Cell::new(0, 0), Cell::new(3, 6)));
+        assert!(!v.sees(Cell::new(0, 0), Cell::new(3, 7)));
+        // 8 in the same position of other blocks, 4 of them in the same row or col
+        assert_eq!(24, count_peers(v, (4, 4)));
+    }
+
+    #[test]
+    fn center_dot() {
+        let v = Variant::CENTER_DOT;
+        assert!(v.sees(Cell::new(1, 1), Cell::new(7, 4)));
+        assert!(!v.sees(Cell::new(0, 0), Cell::new(3, 3)));
+        assert_eq!(24, count_peers(v, (4, 4)));
+        assert_eq!(20, count_peers(v, (4, 3)));
+
+        assert_eq!(Some(Variant::CENTER_DOT), Variant::from_name("center-dot"));
+        assert_eq!(None, Variant::from_name("centre"));
+        assert_eq!(
+            vec!["anti-king", "center-dot"],
+            (Variant::CENTER_DOT | Variant::ANTI_KING).names()
+        );
+        assert_eq!(Vec::<&str>::new(), Variant::CLASSIC.names());
+        // centres are in the same position of blocks
+        assert_eq!(24, count_peers(v | Variant::DISJOINT, (1, 1)));
+    }
+}